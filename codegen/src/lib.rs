@@ -0,0 +1,348 @@
+//! Code generation for trek-router.
+//!
+//! `#[resource]` and `#[resources]` implement `Resource` and `Resources` for
+//! the type of an `impl` block from the action methods it has, e.g. `show`.
+//! Custom actions are marked with `#[member(METHOD)]` or
+//! `#[collection(METHOD)]`. Async actions are boxed, the body is a
+//! `BoxFuture` of the output.
+//!
+//! `#[get("/users/:id")]`, `#[post(..)]` etc. attach a route to a handler
+//! function, registered by `routes!`.
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{
+    parse_macro_input, Error, FnArg, Ident, ImplItem, ItemFn, ItemImpl, LitStr, Path, ReturnType,
+    Token, Type,
+};
+
+const RESOURCE_ACTIONS: &[&str] = &["show", "create", "update", "delete", "edit", "new"];
+const RESOURCES_ACTIONS: &[&str] = &["index", "create", "new", "show", "update", "delete", "edit"];
+
+/// Implements `Resource` for the type of the `impl` block.
+///
+/// ```ignore
+/// #[resource]
+/// impl Geocoder {
+///     fn show(ctx: Context) -> Body { .. }
+///
+///     #[member(POST)]
+///     fn activate(ctx: Context) -> Body { .. }
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn resource(args: TokenStream, input: TokenStream) -> TokenStream {
+    let item = parse_macro_input!(input as ItemImpl);
+    expand(args.into(), item, false)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Implements `Resources` for the type of the `impl` block.
+///
+/// ```ignore
+/// #[resources]
+/// impl Users {
+///     fn index(ctx: Context) -> Body { .. }
+///     fn show(ctx: Context) -> Body { .. }
+///
+///     #[member(POST)]
+///     fn activate(ctx: Context) -> Body { .. }
+///
+///     #[collection(GET)]
+///     fn search(ctx: Context) -> Body { .. }
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn resources(args: TokenStream, input: TokenStream) -> TokenStream {
+    let item = parse_macro_input!(input as ItemImpl);
+    expand(args.into(), item, true)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+macro_rules! route_attributes {
+    ($($(#[$doc:meta])* $name:ident => $method:ident,)*) => {
+        $(
+            $(#[$doc])*
+            #[proc_macro_attribute]
+            pub fn $name(args: TokenStream, input: TokenStream) -> TokenStream {
+                let path = parse_macro_input!(args as LitStr);
+                let item = parse_macro_input!(input as ItemFn);
+                route(stringify!($method), path, item).into()
+            }
+        )*
+    };
+}
+
+route_attributes! {
+    /// Routes `GET` requests of the path to the handler function.
+    ///
+    /// ```ignore
+    /// #[get("/users/:id")]
+    /// fn show_user(ctx: Context) -> Body { .. }
+    ///
+    /// router.scope("/", routes![show_user]);
+    /// ```
+    get => GET,
+    /// Routes `POST` requests of the path to the handler function.
+    post => POST,
+    /// Routes `PUT` requests of the path to the handler function.
+    put => PUT,
+    /// Routes `PATCH` requests of the path to the handler function.
+    patch => PATCH,
+    /// Routes `DELETE` requests of the path to the handler function.
+    delete => DELETE,
+    /// Routes `HEAD` requests of the path to the handler function.
+    head => HEAD,
+    /// Routes `OPTIONS` requests of the path to the handler function.
+    options => OPTIONS,
+}
+
+// keeps the function and adds a hidden struct holding the route for
+// `routes!`, named after the function so it doesn't clash with other items
+fn route(method: &str, path: LitStr, item: ItemFn) -> TokenStream2 {
+    let vis = &item.vis;
+    let name = route_ident(&item.sig.ident);
+    let method = Ident::new(method, path.span());
+    quote! {
+        #item
+
+        #[doc(hidden)]
+        #[allow(non_camel_case_types)]
+        #vis struct #name {}
+
+        impl #name {
+            #[doc(hidden)]
+            pub const METHOD: ::trek_router::__private::Method =
+                ::trek_router::__private::Method::#method;
+            // validated at compile time, when `routes!` uses it
+            #[doc(hidden)]
+            pub const PATH: &'static str = ::trek_router::Pattern::new(#path).as_str();
+        }
+    }
+}
+
+fn route_ident(name: &Ident) -> Ident {
+    format_ident!("__trek_route_{}", name)
+}
+
+/// Registers handler functions annotated with `#[get("/path")]`, `#[post(..)]`
+/// etc.
+///
+/// Expands to a builder for `Router::scope`, the paths are relative to the
+/// scope:
+///
+/// ```ignore
+/// router.scope("/api", routes![list_users, users::show]);
+/// ```
+#[proc_macro]
+pub fn routes(input: TokenStream) -> TokenStream {
+    let handlers = parse_macro_input!(input with Punctuated::<Path, Token![,]>::parse_terminated);
+    let routes = handlers.iter().map(|handler| {
+        let mut route = handler.clone();
+        if let Some(last) = route.segments.last_mut() {
+            last.ident = route_ident(&last.ident);
+        }
+        quote! {
+            router.handle(#route::METHOD, #route::PATH, #handler);
+        }
+    });
+    quote!(
+        |router: &mut ::trek_router::Router<_>| {
+            #(#routes)*
+        }
+    )
+    .into()
+}
+
+// a custom action, marked with `#[member(..)]` or `#[collection(..)]`
+struct Custom {
+    name: Ident,
+    method: Ident,
+    member: bool,
+    asyncness: bool,
+}
+
+fn expand(args: TokenStream2, mut item: ItemImpl, plural: bool) -> Result<TokenStream2, Error> {
+    if !args.is_empty() {
+        return Err(Error::new_spanned(args, "unexpected arguments"));
+    }
+    let table = if plural {
+        RESOURCES_ACTIONS
+    } else {
+        RESOURCE_ACTIONS
+    };
+
+    let mut actions = Vec::new();
+    let mut customs = Vec::new();
+    let mut types = None;
+
+    for impl_item in item.items.iter_mut() {
+        let f = match impl_item {
+            ImplItem::Fn(f) => f,
+            _ => continue,
+        };
+        let name = f.sig.ident.clone();
+
+        let mut custom = None;
+        let mut attrs = Vec::new();
+        for attr in f.attrs.drain(..) {
+            let member = attr.path().is_ident("member");
+            if member || attr.path().is_ident("collection") {
+                if custom.is_some() {
+                    return Err(Error::new_spanned(attr, "duplicate custom action"));
+                }
+                if !member && !plural {
+                    return Err(Error::new_spanned(
+                        attr,
+                        "a singular resource has no collection actions",
+                    ));
+                }
+                custom = Some(Custom {
+                    name: name.clone(),
+                    method: attr.parse_args()?,
+                    member,
+                    asyncness: f.sig.asyncness.is_some(),
+                });
+            } else {
+                attrs.push(attr);
+            }
+        }
+        f.attrs = attrs;
+
+        let is_action = table.iter().any(|a| name == a);
+        if custom.is_none() && !is_action {
+            if !plural && name == "index" {
+                return Err(Error::new_spanned(
+                    name,
+                    "a singular resource has no `index` action",
+                ));
+            }
+            continue;
+        }
+
+        if is_action && name == "new" {
+            // the `new` action returns the body
+            f.attrs
+                .push(syn::parse_quote!(#[allow(clippy::new_ret_no_self)]));
+        }
+
+        // mismatched types of the other actions are reported by the compiler
+        let (context, body) = signature(&f.sig)?;
+        types.get_or_insert((context, body));
+        match custom {
+            Some(custom) => customs.push(custom),
+            None => actions.push((name, f.sig.asyncness.is_some())),
+        }
+    }
+
+    let (context, body) = match types {
+        Some(types) => types,
+        None => {
+            return Err(Error::new_spanned(
+                &item.self_ty,
+                "no actions, expected methods like `show(ctx: Context) -> Body`",
+            ))
+        }
+    };
+
+    let self_ty = &item.self_ty;
+    let (impl_generics, _, where_clause) = item.generics.split_for_impl();
+
+    // an async action is boxed, the body is a `BoxFuture`
+    let handler = |name: &Ident, asyncness: bool| {
+        if asyncness {
+            quote! {
+                |ctx| -> Self::Body { ::std::boxed::Box::pin(<#self_ty>::#name(ctx)) }
+            }
+        } else {
+            quote!(<#self_ty>::#name)
+        }
+    };
+
+    let actions = actions.iter().map(|(name, asyncness)| {
+        let handler = handler(name, *asyncness);
+        quote! {
+            fn #name() -> ::std::option::Option<fn(Self::Context) -> Self::Body> {
+                ::std::option::Option::Some(#handler)
+            }
+        }
+    });
+
+    let custom = |member: bool| {
+        customs.iter().filter(move |c| c.member == member).map(|c| {
+            let action = c.name.to_string();
+            let method = &c.method;
+            let handler = handler(&c.name, c.asyncness);
+            quote! {
+                (#action, ::trek_router::__private::Method::#method, #handler)
+            }
+        })
+    };
+    let members = custom(true);
+    let collections = custom(false);
+    let customs = if plural {
+        quote! {
+            fn members() -> ::trek_router::CustomActions<Self::Context, Self::Body> {
+                vec![#(#members),*]
+            }
+
+            fn collections() -> ::trek_router::CustomActions<Self::Context, Self::Body> {
+                vec![#(#collections),*]
+            }
+        }
+    } else {
+        quote! {
+            fn members() -> ::trek_router::CustomActions<Self::Context, Self::Body> {
+                vec![#(#members),*]
+            }
+        }
+    };
+
+    let trait_name = if plural {
+        quote!(::trek_router::Resources)
+    } else {
+        quote!(::trek_router::Resource)
+    };
+
+    Ok(quote! {
+        #item
+
+        impl #impl_generics #trait_name for #self_ty #where_clause {
+            type Context = #context;
+            type Body = #body;
+
+            #(#actions)*
+
+            #customs
+        }
+    })
+}
+
+// the context and body types of an action
+fn signature(sig: &syn::Signature) -> Result<(Type, Type), Error> {
+    let mut inputs = sig.inputs.iter();
+    let context = match (inputs.next(), inputs.next()) {
+        (Some(FnArg::Typed(arg)), None) => (*arg.ty).clone(),
+        _ => {
+            return Err(Error::new(
+                sig.inputs.span(),
+                "an action takes only the context, e.g. `ctx: Context`",
+            ))
+        }
+    };
+    let body = match &sig.output {
+        ReturnType::Default => syn::parse_quote!(()),
+        ReturnType::Type(_, ty) => (**ty).clone(),
+    };
+    if sig.asyncness.is_some() {
+        return Ok((context, syn::parse_quote!(::trek_router::BoxFuture<#body>)));
+    }
+    Ok((context, body))
+}
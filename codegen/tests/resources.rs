@@ -0,0 +1,141 @@
+extern crate futures_executor;
+extern crate http;
+extern crate trek_router;
+extern crate trek_router_codegen;
+
+use futures_executor::block_on;
+use http::Method;
+use trek_router::{AsyncHandler, Resource, ResourceOptions, Resources, Router};
+use trek_router_codegen::{resource, resources};
+
+struct Context {
+    count: usize,
+}
+
+struct Geocoder {}
+
+#[resource]
+impl Geocoder {
+    fn show(ctx: Context) -> usize {
+        ctx.count
+    }
+
+    fn update(ctx: Context) -> usize {
+        ctx.count + 2
+    }
+
+    #[member(POST)]
+    fn verify(ctx: Context) -> usize {
+        ctx.count + 6
+    }
+}
+
+struct Users {}
+
+#[resources]
+impl Users {
+    fn index(ctx: Context) -> usize {
+        ctx.count
+    }
+
+    fn new(ctx: Context) -> usize {
+        ctx.count + 2
+    }
+
+    fn show(ctx: Context) -> usize {
+        ctx.count + 3
+    }
+
+    #[member(POST)]
+    fn activate(ctx: Context) -> usize {
+        ctx.count + 7
+    }
+
+    #[collection(GET)]
+    fn search(ctx: Context) -> usize {
+        ctx.count + 9
+    }
+
+    // not an action
+    fn helper() -> usize {
+        42
+    }
+}
+
+#[test]
+fn resources() {
+    type F = fn(Context) -> usize;
+    let mut router = Router::<F>::new();
+
+    router
+        .resource(
+            "/geocoder",
+            Geocoder::build(ResourceOptions::default()).unwrap(),
+        )
+        .resources("/users", Users::build(ResourceOptions::default()).unwrap());
+
+    let routes: Vec<_> = router
+        .routes()
+        .map(|r| (r.action().unwrap(), r.method().as_str(), r.path()))
+        .collect();
+    assert_eq!(
+        routes,
+        [
+            ("show", "GET", "/geocoder"),
+            ("update", "PATCH", "/geocoder"),
+            ("update", "PUT", "/geocoder"),
+            ("verify", "POST", "/geocoder/verify"),
+            ("index", "GET", "/users"),
+            ("new", "GET", "/users/new"),
+            ("show", "GET", "/users/:user_id"),
+            ("search", "GET", "/users/search"),
+            ("activate", "POST", "/users/:user_id/activate"),
+        ]
+    );
+
+    let (h, _) = router.find(&Method::GET, "/users/1").unwrap();
+    assert_eq!(h(Context { count: 1 }), 4);
+
+    let (h, _) = router.find(&Method::POST, "/geocoder/verify").unwrap();
+    assert_eq!(h(Context { count: 1 }), 7);
+
+    assert_eq!(Users::helper(), 42);
+}
+
+struct Photos {}
+
+#[resources]
+impl Photos {
+    async fn index(ctx: Context) -> usize {
+        ctx.count
+    }
+
+    async fn show(ctx: Context) -> usize {
+        ctx.count + 3
+    }
+
+    #[member(POST)]
+    async fn rotate(ctx: Context) -> usize {
+        ctx.count + 8
+    }
+}
+
+#[test]
+fn async_resources() {
+    let mut router = Router::<AsyncHandler<Context, usize>>::new();
+
+    let photos = Photos::build(ResourceOptions::default()).unwrap();
+    router.resources(
+        "/photos",
+        photos
+            .into_iter()
+            .map(|r| r.map(AsyncHandler::from))
+            .collect(),
+    );
+
+    let (h, _) = router.find(&Method::GET, "/photos/1").unwrap();
+    assert_eq!(block_on(h.call(Context { count: 1 })), 4);
+
+    let (h, _) = router.find(&Method::POST, "/photos/1/rotate").unwrap();
+    assert_eq!(block_on(h.call(Context { count: 1 })), 9);
+}
@@ -0,0 +1,97 @@
+extern crate futures_executor;
+extern crate http;
+extern crate trek_router;
+extern crate trek_router_codegen;
+
+use futures_executor::block_on;
+use http::Method;
+use trek_router::{AsyncHandler, BoxHandler, Router};
+use trek_router_codegen::{get, post, routes};
+
+type F = fn(usize) -> String;
+
+#[get("/users")]
+fn list_users(_: usize) -> String {
+    "list".to_owned()
+}
+
+#[post("/users")]
+fn create_user(_: usize) -> String {
+    "create".to_owned()
+}
+
+// a module of the same name as the handler
+#[get("/health")]
+fn health(_: usize) -> String {
+    health::OK.to_owned()
+}
+
+mod health {
+    pub const OK: &str = "ok";
+}
+
+#[get("/ping")]
+async fn ping(n: usize) -> String {
+    format!("pong {}", n)
+}
+
+mod users {
+    use trek_router_codegen::{delete, get};
+
+    #[get("/users/:id")]
+    pub fn show(id: usize) -> String {
+        format!("show {}", id)
+    }
+
+    #[delete("/users/:id")]
+    pub fn delete(id: usize) -> String {
+        format!("delete {}", id)
+    }
+}
+
+#[test]
+fn routes() {
+    let mut router = Router::<F>::new();
+    router.scope(
+        "/api",
+        routes![list_users, create_user, users::show, users::delete, health,],
+    );
+
+    let routes: Vec<_> = router
+        .routes()
+        .map(|r| (r.method().as_str(), r.path()))
+        .collect();
+    assert_eq!(
+        routes,
+        [
+            ("GET", "/api/users"),
+            ("POST", "/api/users"),
+            ("GET", "/api/users/:id"),
+            ("DELETE", "/api/users/:id"),
+            ("GET", "/api/health"),
+        ]
+    );
+
+    let (h, p) = router.find(&Method::GET, "/api/users/1").unwrap();
+    assert_eq!(h(p.parse("id").unwrap()), "show 1");
+
+    let (h, _) = router.find(&Method::POST, "/api/users").unwrap();
+    assert_eq!(h(0), "create");
+
+    assert_eq!(list_users(0), "list");
+}
+
+#[test]
+fn handler_routers() {
+    let mut router = Router::<BoxHandler<usize, String>>::new();
+    router.scope("/", routes![list_users, users::show]);
+
+    let (h, _) = router.find(&Method::GET, "/users/1").unwrap();
+    assert_eq!(h.call(1), "show 1");
+
+    let mut router = Router::<AsyncHandler<usize, String>>::new();
+    router.scope("/", routes![ping]);
+
+    let (h, _) = router.find(&Method::GET, "/ping").unwrap();
+    assert_eq!(block_on(h.call(2)), "pong 2");
+}
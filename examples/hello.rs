@@ -4,53 +4,51 @@ extern crate trek_router;
 
 use futures::Future;
 use hyper::server::Server;
-use hyper::service::service_fn_ok;
-use hyper::{Body, Request, Response, StatusCode};
-use std::sync::Arc;
-use trek_router::Router;
+use hyper::{Body, Request, Response};
+use trek_router::hyper::RouterService;
+use trek_router::{Params, Router};
 
-type Params<'a> = Vec<(&'a str, &'a str)>;
+type Handler = fn(Request<Body>) -> Response<Body>;
 
-type Handler = fn(Request<Body>, Params) -> Body;
-
-fn v1_login(_: Request<Body>, _: Params) -> Body {
-    Body::from("v1 login")
+fn v1_login(_: Request<Body>) -> Response<Body> {
+    Response::new(Body::from("v1 login"))
 }
 
-fn v1_submit(_req: Request<Body>, _: Params) -> Body {
-    Body::from("v1 submit")
+fn v1_submit(_: Request<Body>) -> Response<Body> {
+    Response::new(Body::from("v1 submit"))
 }
 
-fn v1_read(_req: Request<Body>, _: Params) -> Body {
-    Body::from("v1 read")
+fn v1_read(_: Request<Body>) -> Response<Body> {
+    Response::new(Body::from("v1 read"))
 }
 
-fn v2_login(_: Request<Body>, _: Params) -> Body {
-    Body::from("v2 login")
+fn v2_login(_: Request<Body>) -> Response<Body> {
+    Response::new(Body::from("v2 login"))
 }
 
-fn v2_submit(_req: Request<Body>, _: Params) -> Body {
-    Body::from("v2 submit")
+fn v2_submit(_: Request<Body>) -> Response<Body> {
+    Response::new(Body::from("v2 submit"))
 }
 
-fn v2_read(_req: Request<Body>, _: Params) -> Body {
-    Body::from("v2 read")
+fn v2_read(_: Request<Body>) -> Response<Body> {
+    Response::new(Body::from("v2 read"))
 }
 
-fn users(_req: Request<Body>, _: Params) -> Body {
-    Body::from("users")
+fn user(req: Request<Body>) -> Response<Body> {
+    let params = req.extensions().get::<Params>().unwrap();
+    Response::new(Body::from(format!("user {}", params.get("id").unwrap())))
 }
 
-fn foo(_: Request<Body>, _: Params) -> Body {
-    Body::from("foo")
+fn foo(_: Request<Body>) -> Response<Body> {
+    Response::new(Body::from("foo"))
 }
 
-fn bar(_req: Request<Body>, _: Params) -> Body {
-    Body::from("bar")
+fn bar(_: Request<Body>) -> Response<Body> {
+    Response::new(Body::from("bar"))
 }
 
-fn baz(_req: Request<Body>, _: Params) -> Body {
-    Body::from("baz")
+fn baz(_: Request<Body>) -> Response<Body> {
+    Response::new(Body::from("baz"))
 }
 
 fn main() {
@@ -72,34 +70,15 @@ fn main() {
                 .delete("/read", v2_read)
                 // scope users
                 .scope("users", |u| {
-                    u.any("", users);
+                    u.any("/:id", user);
                 });
         })
         .get("/foo", foo)
         .post("/bar", bar)
         .delete("/baz", baz);
 
-    let router = Arc::new(router);
-
-    let routing = move || {
-        let router = Arc::clone(&router);
-
-        service_fn_ok(move |req| {
-            let method = req.method().to_owned();
-            let path = req.uri().path().to_owned();
-
-            match router.find(&method, &path) {
-                Some((handler, params)) => Response::new(handler(req, params)),
-                None => Response::builder()
-                    .status(StatusCode::NOT_FOUND)
-                    .body(Body::from("Not Found"))
-                    .unwrap(),
-            }
-        })
-    };
-
     let server = Server::bind(&addr)
-        .serve(routing)
+        .serve(RouterService::new(router))
         .map_err(|e| eprintln!("server error: {}", e));
 
     hyper::rt::run(server);
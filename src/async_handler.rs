@@ -0,0 +1,142 @@
+use crate::IntoRoute;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// A boxed future, the result of an `AsyncHandler` and the `Body` of async
+/// actions generated by `#[resource]` and `#[resources]`.
+pub type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send + 'static>>;
+
+/// A handler of async functions, so a `Router<AsyncHandler<C, B>>` routes
+/// `async fn(C) -> B` and closures returning futures, see `IntoRoute`:
+///
+/// ```
+/// # use trek_router::{AsyncHandler, Router};
+/// async fn login(user: String) -> usize {
+///     user.len()
+/// }
+///
+/// let mut router = Router::<AsyncHandler<String, usize>>::new();
+/// router
+///     .get("/login", login)
+///     .post("/logout", |_| async { 0 });
+/// ```
+///
+/// Middleware awaits the next handler inside its own future, see
+/// `Middleware`. With the `tower` feature it is a `Service`.
+pub struct AsyncHandler<C, B>(Arc<dyn Fn(C) -> BoxFuture<B> + Send + Sync>);
+
+impl<C, B> AsyncHandler<C, B> {
+    pub fn new<F, Fut>(f: F) -> Self
+    where
+        F: Fn(C) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = B> + Send + 'static,
+    {
+        AsyncHandler(Arc::new(move |ctx| Box::pin(f(ctx))))
+    }
+
+    pub fn call(&self, ctx: C) -> BoxFuture<B> {
+        (self.0)(ctx)
+    }
+}
+
+// a boxed action of an async resource, e.g. `Users::build(opts)` mapped with
+// `ResourceRoute::map`, is not boxed again
+impl<C, B> From<fn(C) -> BoxFuture<B>> for AsyncHandler<C, B>
+where
+    C: 'static,
+    B: 'static,
+{
+    fn from(f: fn(C) -> BoxFuture<B>) -> Self {
+        AsyncHandler(Arc::new(f))
+    }
+}
+
+impl<C, B> Clone for AsyncHandler<C, B> {
+    fn clone(&self) -> Self {
+        AsyncHandler(Arc::clone(&self.0))
+    }
+}
+
+impl<C, B> fmt::Debug for AsyncHandler<C, B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("AsyncHandler")
+    }
+}
+
+impl<C, B, F, Fut> IntoRoute<F, (C,)> for AsyncHandler<C, B>
+where
+    F: Fn(C) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = B> + Send + 'static,
+{
+    fn into_route(handler: F) -> Self {
+        AsyncHandler::new(handler)
+    }
+}
+
+#[cfg(feature = "tower")]
+impl<C, B> tower_service::Service<C> for AsyncHandler<C, B>
+where
+    B: 'static,
+{
+    type Response = B;
+    type Error = std::convert::Infallible;
+    type Future = BoxFuture<Result<B, Self::Error>>;
+
+    fn poll_ready(
+        &mut self,
+        _: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, ctx: C) -> Self::Future {
+        let future = AsyncHandler::call(self, ctx);
+        Box::pin(async move { Ok(future.await) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Router;
+    use futures_executor::block_on;
+    use http::Method;
+
+    async fn hello(name: String) -> String {
+        format!("Hello, {}!", name)
+    }
+
+    #[test]
+    fn async_handler() {
+        type H = AsyncHandler<String, String>;
+        let mut router = Router::<H>::new();
+
+        router
+            .middleware(|next: H| -> H {
+                AsyncHandler::new(move |name: String| {
+                    let next = next.clone();
+                    async move {
+                        let body = next.call(name.to_uppercase()).await;
+                        body + " (m1)"
+                    }
+                })
+            })
+            .get("/hello", hello)
+            .post("/hi", |name| async move { format!("Hi, {}!", name) })
+            .put(
+                "/wrapped",
+                AsyncHandler::new(|name| async move { format!("Hey, {}!", name) }),
+            );
+
+        let (h, _) = router.find(&Method::GET, "/hello").unwrap();
+        assert_eq!(block_on(h.call("trek".to_owned())), "Hello, TREK! (m1)");
+
+        let (h, _) = router.find(&Method::POST, "/hi").unwrap();
+        assert_eq!(block_on(h.call("trek".to_owned())), "Hi, TREK! (m1)");
+
+        let (h, _) = router.find(&Method::PUT, "/wrapped").unwrap();
+        assert_eq!(block_on(h.call("trek".to_owned())), "Hey, TREK! (m1)");
+    }
+}
@@ -0,0 +1,77 @@
+#[cfg(feature = "regex")]
+use regex::Regex;
+
+/// Restricts the values a route parameter accepts, see `Router::constraint`.
+#[derive(Clone, Debug)]
+pub enum Constraint {
+    /// ASCII digits, e.g. `42`.
+    Digits,
+    /// A hyphenated UUID, e.g. `67e55044-10b1-426f-9247-bb680e5fe0c8`.
+    Uuid,
+    /// Lowercase ASCII letters, digits and single hyphens, e.g. `hello-world`.
+    Slug,
+    /// A regex matching the whole value, with the `regex` feature.
+    #[cfg(feature = "regex")]
+    Regex(Regex),
+}
+
+impl Constraint {
+    /// A regex constraint, anchored to match the whole value.
+    #[cfg(feature = "regex")]
+    pub fn regex(re: &str) -> Result<Self, regex::Error> {
+        Regex::new(&format!("^(?:{})$", re)).map(Constraint::Regex)
+    }
+
+    pub fn matches(&self, value: &str) -> bool {
+        match self {
+            Constraint::Digits => !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()),
+            Constraint::Uuid => {
+                value.len() == 36
+                    && value.bytes().enumerate().all(|(i, b)| match i {
+                        8 | 13 | 18 | 23 => b == b'-',
+                        _ => b.is_ascii_hexdigit(),
+                    })
+            }
+            Constraint::Slug => {
+                !value.is_empty()
+                    && value.split('-').all(|s| {
+                        !s.is_empty()
+                            && s.bytes()
+                                .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit())
+                    })
+            }
+            #[cfg(feature = "regex")]
+            Constraint::Regex(re) => re.is_match(value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches() {
+        assert!(Constraint::Digits.matches("42"));
+        assert!(!Constraint::Digits.matches(""));
+        assert!(!Constraint::Digits.matches("new"));
+
+        assert!(Constraint::Uuid.matches("67e55044-10b1-426f-9247-bb680e5fe0c8"));
+        assert!(!Constraint::Uuid.matches("67e55044-10b1-426f-9247-bb680e5fe0c"));
+        assert!(!Constraint::Uuid.matches("67e55044x10b1-426f-9247-bb680e5fe0c8"));
+
+        assert!(Constraint::Slug.matches("hello-world-2"));
+        assert!(!Constraint::Slug.matches("hello--world"));
+        assert!(!Constraint::Slug.matches("Hello"));
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn regex() {
+        let re = Constraint::regex(r"[a-z]{2}|\d+").unwrap();
+        assert!(re.matches("en"));
+        assert!(re.matches("42"));
+        assert!(!re.matches("en42"));
+        assert!(Constraint::regex("(").is_err());
+    }
+}
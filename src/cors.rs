@@ -0,0 +1,113 @@
+use http::Method;
+
+/// A CORS policy, applied by `Router::cors` to the routes registered after it.
+///
+/// No origins means any origin is allowed.
+#[derive(Clone, Debug, Default)]
+pub struct Cors {
+    origins: Vec<String>,
+    headers: Vec<String>,
+    max_age: Option<u64>,
+    credentials: bool,
+}
+
+impl Cors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn origin(mut self, origin: &str) -> Self {
+        self.origins.push(origin.to_owned());
+        self
+    }
+
+    pub fn header(mut self, header: &str) -> Self {
+        self.headers.push(header.to_ascii_lowercase());
+        self
+    }
+
+    pub fn max_age(mut self, seconds: u64) -> Self {
+        self.max_age = Some(seconds);
+        self
+    }
+
+    pub fn credentials(mut self, enabled: bool) -> Self {
+        self.credentials = enabled;
+        self
+    }
+
+    fn allows_origin(&self, origin: &str) -> bool {
+        self.origins.is_empty() || self.origins.iter().any(|o| o == origin)
+    }
+
+    fn allows_headers(&self, headers: &[&str]) -> bool {
+        headers
+            .iter()
+            .all(|h| self.headers.iter().any(|a| a.eq_ignore_ascii_case(h)))
+    }
+
+    pub(crate) fn preflight(
+        &self,
+        origin: &str,
+        method: &Method,
+        headers: &[&str],
+        allowed: Vec<Method>,
+    ) -> Option<Preflight> {
+        if !self.allows_origin(origin) || !allowed.contains(method) || !self.allows_headers(headers)
+        {
+            return None;
+        }
+        Some(Preflight {
+            // a wildcard is not allowed together with credentials
+            origin: if self.origins.is_empty() && !self.credentials {
+                "*".to_owned()
+            } else {
+                origin.to_owned()
+            },
+            methods: allowed,
+            headers: self.headers.clone(),
+            max_age: self.max_age,
+            credentials: self.credentials,
+        })
+    }
+}
+
+/// An accepted CORS preflight request, see `Router::preflight`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Preflight {
+    pub origin: String,
+    pub methods: Vec<Method>,
+    pub headers: Vec<String>,
+    pub max_age: Option<u64>,
+    pub credentials: bool,
+}
+
+impl Preflight {
+    /// The `Access-Control-*` response headers.
+    pub fn headers(&self) -> Vec<(&'static str, String)> {
+        let mut headers = vec![
+            ("access-control-allow-origin", self.origin.to_owned()),
+            (
+                "access-control-allow-methods",
+                self.methods
+                    .iter()
+                    .map(Method::as_str)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ),
+        ];
+        if !self.headers.is_empty() {
+            headers.push(("access-control-allow-headers", self.headers.join(", ")));
+        }
+        if let Some(max_age) = self.max_age {
+            headers.push(("access-control-max-age", max_age.to_string()));
+        }
+        if self.credentials {
+            headers.push(("access-control-allow-credentials", "true".to_owned()));
+        }
+        if self.origin != "*" {
+            headers.push(("vary", "origin".to_owned()));
+        }
+        headers
+    }
+}
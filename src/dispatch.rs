@@ -0,0 +1,86 @@
+use crate::{Match, Params, Preflight, Router};
+use http::header::{
+    HeaderName, ACCESS_CONTROL_REQUEST_HEADERS, ACCESS_CONTROL_REQUEST_METHOD, ALLOW, LOCATION,
+    ORIGIN,
+};
+use http::{Method, Request, Response, StatusCode};
+
+// the outcome of a lookup for the server integrations
+pub(crate) enum Dispatch<H, B> {
+    // the routed handler, the params are owned for the request extensions,
+    // the body of its response is discarded for an automatic HEAD
+    Handler(H, Params<'static>, bool),
+    // `404`, `405`, a redirect or an automatic `OPTIONS`, with an empty body
+    Response(Response<B>),
+}
+
+pub(crate) fn dispatch<H, B, RB>(router: &Router<H>, req: &Request<B>) -> Dispatch<H, RB>
+where
+    H: Clone,
+    RB: Default,
+{
+    let method = req.method();
+    let (status, headers) = match router.lookup(method, req.uri().path()) {
+        Match::Found(h, params) => return Dispatch::Handler(h.clone(), params.into_owned(), false),
+        Match::Head(h, params) => return Dispatch::Handler(h.clone(), params.into_owned(), true),
+        Match::Options(allowed) => {
+            let mut headers = vec![(ALLOW, allow(&allowed))];
+            if let Some(preflight) = preflight(router, req) {
+                headers.extend(
+                    preflight
+                        .headers()
+                        .into_iter()
+                        .map(|(name, value)| (HeaderName::from_static(name), value)),
+                );
+            }
+            (StatusCode::NO_CONTENT, headers)
+        }
+        Match::Redirect(location) => {
+            let status = match *method {
+                Method::GET | Method::HEAD => StatusCode::MOVED_PERMANENTLY,
+                _ => StatusCode::PERMANENT_REDIRECT,
+            };
+            // the query is kept, only the path is redirected
+            let location = match req.uri().query() {
+                Some(query) => format!("{}?{}", location, query),
+                None => location,
+            };
+            (status, vec![(LOCATION, location)])
+        }
+        Match::MethodNotAllowed(allowed) => (
+            StatusCode::METHOD_NOT_ALLOWED,
+            vec![(ALLOW, allow(&allowed))],
+        ),
+        Match::NotFound => (StatusCode::NOT_FOUND, Vec::new()),
+    };
+    let mut res = Response::new(RB::default());
+    *res.status_mut() = status;
+    for (name, value) in headers {
+        if let Ok(value) = value.parse() {
+            res.headers_mut().insert(name, value);
+        }
+    }
+    Dispatch::Response(res)
+}
+
+// checks a CORS preflight request, an `OPTIONS` with an `Origin` and an
+// `Access-Control-Request-Method`
+fn preflight<H: Clone, B>(router: &Router<H>, req: &Request<B>) -> Option<Preflight> {
+    let header = |name| req.headers().get(name).and_then(|v| v.to_str().ok());
+    let origin = header(ORIGIN)?;
+    let method = Method::from_bytes(header(ACCESS_CONTROL_REQUEST_METHOD)?.as_bytes()).ok()?;
+    let headers: Vec<_> = header(ACCESS_CONTROL_REQUEST_HEADERS)
+        .map(|h| {
+            h.split(',')
+                .map(str::trim)
+                .filter(|h| !h.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+    router.preflight(req.uri().path(), origin, &method, &headers)
+}
+
+fn allow(allowed: &[Method]) -> String {
+    let methods: Vec<_> = allowed.iter().map(Method::as_str).collect();
+    methods.join(", ")
+}
@@ -0,0 +1,32 @@
+use http::Method;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RouterError {
+    /// The route matches the same requests as a registered route, e.g.
+    /// `/users/:user_id` and `/users/:id`.
+    Conflict {
+        method: Method,
+        path: String,
+        existing: String,
+    },
+}
+
+impl fmt::Display for RouterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RouterError::Conflict {
+                method,
+                path,
+                existing,
+            } => write!(
+                f,
+                "route `{} {}` conflicts with `{} {}`",
+                method, path, method, existing
+            ),
+        }
+    }
+}
+
+impl Error for RouterError {}
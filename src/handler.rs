@@ -0,0 +1,233 @@
+use crate::async_handler::{AsyncHandler, BoxFuture};
+use std::fmt;
+use std::sync::Arc;
+
+/// A handler of a context `C`, returning the body `B`.
+///
+/// Implemented by functions and closures taking the context, closures may
+/// capture state. A struct implements it for handlers with configuration:
+///
+/// ```
+/// # use trek_router::Handler;
+/// struct Greet {
+///     greeting: &'static str,
+/// }
+///
+/// impl Handler<String, String> for Greet {
+///     fn call(&self, name: String) -> String {
+///         format!("{}, {}!", self.greeting, name)
+///     }
+/// }
+/// ```
+///
+/// Different handlers are mixed in a `Router<BoxHandler<C, B>>`.
+pub trait Handler<C, B>: Send + Sync {
+    fn call(&self, ctx: C) -> B;
+}
+
+impl<C, B, F> Handler<C, B> for F
+where
+    F: Fn(C) -> B + Send + Sync,
+{
+    fn call(&self, ctx: C) -> B {
+        self(ctx)
+    }
+}
+
+impl<C, B> Handler<C, BoxFuture<B>> for AsyncHandler<C, B> {
+    fn call(&self, ctx: C) -> BoxFuture<B> {
+        AsyncHandler::call(self, ctx)
+    }
+}
+
+/// Converts a handler of one of the supported shapes into a `BoxHandler`, `T`
+/// tells the shapes apart:
+///
+/// - a `Handler`, e.g. `fn(C) -> B`, a closure or a struct
+/// - a function or closure ignoring the context, `fn() -> B`
+pub trait IntoHandler<C, B, T> {
+    fn into_handler(self) -> BoxHandler<C, B>;
+}
+
+impl<C, B, H> IntoHandler<C, B, (C,)> for H
+where
+    H: Handler<C, B> + 'static,
+{
+    fn into_handler(self) -> BoxHandler<C, B> {
+        BoxHandler(Arc::new(self))
+    }
+}
+
+impl<C, B, F> IntoHandler<C, B, ()> for F
+where
+    F: Fn() -> B + Send + Sync + 'static,
+{
+    fn into_handler(self) -> BoxHandler<C, B> {
+        BoxHandler(Arc::new(move |_: C| self()))
+    }
+}
+
+/// Converts what `Router::handle`, `get`, `post` etc. take into the handler
+/// `Self` of the router, `T` tells the shapes apart. Any router takes its own
+/// handler, a `Router<AsyncHandler<C, B>>` also takes async functions and a
+/// `Router<BoxHandler<C, B>>` functions and closures.
+pub trait IntoRoute<A, T> {
+    fn into_route(handler: A) -> Self;
+}
+
+// the handler of the router itself
+#[doc(hidden)]
+pub struct Direct;
+
+impl<H> IntoRoute<H, Direct> for H {
+    fn into_route(handler: H) -> H {
+        handler
+    }
+}
+
+/// A boxed `Handler`, so handlers of different types are routed by one
+/// `Router<BoxHandler<C, B>>`. `get`, `post` etc. box functions and closures
+/// of both shapes of `IntoHandler`, other handlers are boxed with `new`:
+///
+/// ```
+/// # use trek_router::{BoxHandler, Handler, Router};
+/// struct Greet;
+///
+/// impl Handler<String, String> for Greet {
+///     fn call(&self, name: String) -> String {
+///         format!("Hello, {}!", name)
+///     }
+/// }
+///
+/// fn index() -> String {
+///     "index".to_owned()
+/// }
+///
+/// fn show(id: String) -> String {
+///     format!("user {}", id)
+/// }
+///
+/// let prefix = "v1".to_owned();
+///
+/// let mut router = Router::<BoxHandler<String, String>>::new();
+/// router
+///     .get("/users", index)
+///     .get("/users/:id", show)
+///     .get("/version", move |_| prefix.clone())
+///     .get("/hello/:name", BoxHandler::new(Greet));
+/// ```
+pub struct BoxHandler<C, B>(Arc<dyn Handler<C, B>>);
+
+impl<C, B> BoxHandler<C, B> {
+    pub fn new<T>(handler: impl IntoHandler<C, B, T>) -> Self {
+        handler.into_handler()
+    }
+
+    pub fn call(&self, ctx: C) -> B {
+        self.0.call(ctx)
+    }
+}
+
+impl<C, B> Handler<C, B> for BoxHandler<C, B> {
+    fn call(&self, ctx: C) -> B {
+        self.0.call(ctx)
+    }
+}
+
+impl<C, B, F> IntoRoute<F, (C,)> for BoxHandler<C, B>
+where
+    F: Fn(C) -> B + Send + Sync + 'static,
+{
+    fn into_route(handler: F) -> Self {
+        BoxHandler(Arc::new(handler))
+    }
+}
+
+impl<C, B, F> IntoRoute<F, ()> for BoxHandler<C, B>
+where
+    F: Fn() -> B + Send + Sync + 'static,
+{
+    fn into_route(handler: F) -> Self {
+        BoxHandler(Arc::new(move |_: C| handler()))
+    }
+}
+
+impl<C, B> Clone for BoxHandler<C, B> {
+    fn clone(&self) -> Self {
+        BoxHandler(Arc::clone(&self.0))
+    }
+}
+
+impl<C, B> fmt::Debug for BoxHandler<C, B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("BoxHandler")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ResourceOptions, Resources, Router};
+    use http::Method;
+
+    type H = BoxHandler<usize, String>;
+
+    struct Repeat {
+        s: &'static str,
+    }
+
+    impl Handler<usize, String> for Repeat {
+        fn call(&self, n: usize) -> String {
+            self.s.repeat(n)
+        }
+    }
+
+    fn index() -> String {
+        "index".to_owned()
+    }
+
+    fn show(id: usize) -> String {
+        format!("show {}", id)
+    }
+
+    struct Users {}
+
+    impl Resources for Users {
+        type Context = usize;
+        type Body = String;
+
+        fn index() -> Option<fn(usize) -> String> {
+            Some(|_| "users".to_owned())
+        }
+    }
+
+    #[test]
+    fn handlers() {
+        let mut router = Router::<H>::new();
+        let prefix = "v".to_owned();
+
+        router
+            .middleware(|next: H| -> H { BoxHandler::new(move |n: usize| next.call(n + 1)) })
+            .get("/", index)
+            .get("/show", show)
+            .get("/version", move |n| format!("{}{}", prefix, n))
+            .get("/repeat", BoxHandler::new(Repeat { s: "ab" }))
+            .get("/boxed", BoxHandler::new(|n: usize| n.to_string()))
+            .resources(
+                "/users",
+                Users::build(ResourceOptions::default())
+                    .unwrap()
+                    .into_iter()
+                    .map(|r| r.map(BoxHandler::new))
+                    .collect(),
+            );
+
+        let call = |path| router.find(&Method::GET, path).unwrap().0.call(1);
+        assert_eq!(call("/"), "index");
+        assert_eq!(call("/show"), "show 2");
+        assert_eq!(call("/version"), "v2");
+        assert_eq!(call("/repeat"), "abab");
+        assert_eq!(call("/boxed"), "2");
+        assert_eq!(call("/users"), "users");
+    }
+}
@@ -0,0 +1,215 @@
+//! A Hyper `Service` dispatching requests to a router of handlers.
+
+use crate::dispatch::{dispatch, Dispatch};
+use crate::{Handler, Router};
+use futures::future::{self, FutureResult};
+use hyper::service::{NewService, Service};
+use hyper::{Body, Request, Response};
+use std::sync::Arc;
+
+/// Dispatches a request to the handler routed by `Router::lookup`, with the
+/// `Params` in the request extensions. Other matches are answered as listed on
+/// `Match`.
+///
+/// It is also a `NewService`, served with `Server::bind(&addr).serve(service)`.
+pub struct RouterService<H> {
+    router: Arc<Router<H>>,
+}
+
+impl<H> RouterService<H> {
+    pub fn new(router: Router<H>) -> Self {
+        Self {
+            router: Arc::new(router),
+        }
+    }
+}
+
+impl<H> Clone for RouterService<H> {
+    fn clone(&self) -> Self {
+        Self {
+            router: Arc::clone(&self.router),
+        }
+    }
+}
+
+impl<H> Service for RouterService<H>
+where
+    H: Handler<Request<Body>, Response<Body>> + Clone,
+{
+    type ReqBody = Body;
+    type ResBody = Body;
+    type Error = hyper::Error;
+    type Future = FutureResult<Response<Body>, hyper::Error>;
+
+    fn call(&mut self, mut req: Request<Body>) -> Self::Future {
+        let res = match dispatch(&self.router, &req) {
+            Dispatch::Handler(handler, params, head) => {
+                req.extensions_mut().insert(params);
+                let res = handler.call(req);
+                // the GET handler answers an automatic HEAD
+                if head {
+                    res.map(|_| Body::empty())
+                } else {
+                    res
+                }
+            }
+            Dispatch::Response(res) => res,
+        };
+        future::ok(res)
+    }
+}
+
+impl<H> NewService for RouterService<H>
+where
+    H: Handler<Request<Body>, Response<Body>> + Clone,
+{
+    type ReqBody = Body;
+    type ResBody = Body;
+    type Error = hyper::Error;
+    type Service = Self;
+    type Future = FutureResult<Self, Self::InitError>;
+    type InitError = hyper::Error;
+
+    fn new_service(&self) -> Self::Future {
+        future::ok(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BoxHandler, Cors, Params};
+    use futures::{Future, Stream};
+    use http::header::{
+        ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_REQUEST_METHOD,
+        ALLOW, LOCATION, ORIGIN,
+    };
+    use http::{HeaderMap, Method, StatusCode};
+
+    type F = fn(Request<Body>) -> Response<Body>;
+
+    fn hello(req: Request<Body>) -> Response<Body> {
+        let params = req.extensions().get::<Params>().unwrap();
+        let name = params.get("name").unwrap_or("world");
+        Response::new(Body::from(format!("Hello, {}!", name)))
+    }
+
+    fn call<H>(
+        service: &mut RouterService<H>,
+        method: Method,
+        path: &str,
+    ) -> (StatusCode, HeaderMap, String)
+    where
+        H: Handler<Request<Body>, Response<Body>> + Clone,
+    {
+        let req = Request::builder()
+            .method(method)
+            .uri(path)
+            .body(Body::empty())
+            .unwrap();
+        send(service, req)
+    }
+
+    fn send<H>(
+        service: &mut RouterService<H>,
+        req: Request<Body>,
+    ) -> (StatusCode, HeaderMap, String)
+    where
+        H: Handler<Request<Body>, Response<Body>> + Clone,
+    {
+        let res = service.call(req).wait().unwrap();
+        let (parts, body) = res.into_parts();
+        let body = body.concat2().wait().unwrap();
+        (
+            parts.status,
+            parts.headers,
+            String::from_utf8(body.to_vec()).unwrap(),
+        )
+    }
+
+    #[test]
+    fn service() {
+        let mut router = Router::<F>::new();
+        router
+            .redirect_trailing_slash(true)
+            .auto_head(true)
+            .get("/", hello)
+            .get("/hello/:name", hello);
+        let mut service = RouterService::new(router).new_service().wait().unwrap();
+
+        let (status, _, body) = call(&mut service, Method::GET, "/hello/trek");
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "Hello, trek!");
+
+        let (_, _, body) = call(&mut service, Method::GET, "/");
+        assert_eq!(body, "Hello, world!");
+
+        let (status, _, body) = call(&mut service, Method::HEAD, "/hello/trek");
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "");
+
+        let (status, _, body) = call(&mut service, Method::GET, "/users");
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body, "");
+
+        let (status, headers, _) = call(&mut service, Method::POST, "/hello/trek");
+        assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(headers[ALLOW], "GET, HEAD");
+
+        let (status, headers, _) = call(&mut service, Method::GET, "/hello/trek/");
+        assert_eq!(status, StatusCode::MOVED_PERMANENTLY);
+        assert_eq!(headers[LOCATION], "/hello/trek");
+
+        let (_, headers, _) = call(&mut service, Method::GET, "/hello/trek/?lang=en");
+        assert_eq!(headers[LOCATION], "/hello/trek?lang=en");
+    }
+
+    #[test]
+    fn preflight() {
+        let mut router = Router::<F>::new();
+        router
+            .auto_options(true)
+            .cors(Cors::new().origin("https://example.com"))
+            .get("/hello/:name", hello);
+        let mut service = RouterService::new(router);
+
+        let req = Request::builder()
+            .method(Method::OPTIONS)
+            .uri("/hello/trek")
+            .header(ORIGIN, "https://example.com")
+            .header(ACCESS_CONTROL_REQUEST_METHOD, "GET")
+            .body(Body::empty())
+            .unwrap();
+        let (status, headers, _) = send(&mut service, req);
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        assert_eq!(headers[ALLOW], "GET, OPTIONS");
+        assert_eq!(headers[ACCESS_CONTROL_ALLOW_ORIGIN], "https://example.com");
+        assert_eq!(headers[ACCESS_CONTROL_ALLOW_METHODS], "GET, OPTIONS");
+
+        // a plain OPTIONS has no CORS headers
+        let (status, headers, _) = call(&mut service, Method::OPTIONS, "/hello/trek");
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        assert!(!headers.contains_key(ACCESS_CONTROL_ALLOW_ORIGIN));
+    }
+
+    #[test]
+    fn box_handler() {
+        let greeting = "Hi".to_owned();
+        let mut router = Router::<BoxHandler<Request<Body>, Response<Body>>>::new();
+        router
+            .get("/hello/:name", hello)
+            .get("/hi", move |_| Response::new(Body::from(greeting.clone())))
+            .get("/ping", || Response::new(Body::from("pong")));
+        let mut service = RouterService::new(router);
+
+        let (status, _, body) = call(&mut service, Method::GET, "/hello/trek");
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "Hello, trek!");
+
+        let (_, _, body) = call(&mut service, Method::GET, "/hi");
+        assert_eq!(body, "Hi");
+
+        let (_, _, body) = call(&mut service, Method::GET, "/ping");
+        assert_eq!(body, "pong");
+    }
+}
@@ -1,26 +1,232 @@
 #[macro_use]
 extern crate lazy_static;
+#[cfg(feature = "hyper")]
+extern crate futures;
 extern crate http;
 extern crate inflector;
 extern crate path_tree;
+#[cfg(feature = "regex")]
+extern crate regex;
+#[cfg(feature = "tower")]
+extern crate tower_service;
+#[cfg(feature = "codegen")]
+extern crate trek_router_codegen;
 
+mod async_handler;
+mod constraint;
+mod cors;
+#[cfg(any(feature = "tower", feature = "hyper"))]
+mod dispatch;
+mod error;
+mod handler;
+#[cfg(feature = "hyper")]
+pub mod hyper;
+mod macros;
+mod middleware;
+mod params;
+mod pattern;
 mod resource;
+mod route;
+#[cfg(feature = "tower")]
+pub mod tower;
 
 use http::Method;
-use inflector::string::pluralize::to_plural;
-use inflector::string::singularize::to_singular;
+use params::{decode, encode};
 use path_tree::PathTree;
 use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
 
-pub use resource::{Resource, ResourceOptions, Resources};
+pub use async_handler::{AsyncHandler, BoxFuture};
+pub use constraint::Constraint;
+pub use cors::{Cors, Preflight};
+pub use error::RouterError;
+pub use handler::{BoxHandler, Handler, IntoHandler, IntoRoute};
+pub use middleware::Middleware;
+pub use params::{ParamError, Params};
+pub use pattern::{Pattern, PatternError};
+pub use resource::{
+    Action, Actions, CustomActions, Resource, ResourceError, ResourceName, ResourceOptions,
+    ResourceRoute, Resources, SharedActions, SharedCustomActions, SharedFn, SharedHandler,
+    SharedResource, SharedResources,
+};
+pub use route::{Route, RouteTable};
+#[cfg(feature = "codegen")]
+pub use trek_router_codegen::{
+    delete, get, head, options, patch, post, put, resource, resources, routes,
+};
 
+// used by the generated code of `trek-router-codegen`
+#[doc(hidden)]
+pub mod __private {
+    pub use http::Method;
+}
+
+/// The trees of the handlers by method.
+#[deprecated(note = "the trees of a `Router` are private, see `Router::routes`")]
 pub type Trees<H> = HashMap<Method, PathTree<H>>;
 
+// the routes of a method, the tree indexes the endpoints
+#[derive(Clone, Debug)]
+pub(crate) struct Table<H> {
+    tree: PathTree<usize>,
+    endpoints: Vec<Endpoint<H>>,
+}
+
+impl<H> Default for Table<H> {
+    fn default() -> Self {
+        Self {
+            tree: PathTree::new(),
+            endpoints: Vec::new(),
+        }
+    }
+}
+
+impl<H> Table<H> {
+    fn insert(&mut self, endpoint: Endpoint<H>) {
+        match self.endpoints.iter().position(|e| e.path == endpoint.path) {
+            Some(i) => self.endpoints[i] = endpoint,
+            None => {
+                self.tree.insert(&endpoint.path, self.endpoints.len());
+                self.endpoints.push(endpoint);
+            }
+        }
+    }
+
+    fn find<'a>(&'a self, path: &'a str) -> Option<(&'a H, Params<'a>)> {
+        let (&i, p) = self.tree.find(path)?;
+        let e = &self.endpoints[i];
+        if e.accepts(&p) {
+            return Some((&e.handler, p.into()));
+        }
+        // the tree only knows the best match, a route it shadows, e.g. a
+        // catch-all, may accept the params
+        self.endpoints
+            .iter()
+            .filter_map(|e| {
+                let p = captures(&e.path, path)?;
+                if e.accepts(&p) {
+                    Some((e, p))
+                } else {
+                    None
+                }
+            })
+            .min_by_key(|(e, _)| rank(&e.path))
+            .map(|(e, p)| (&e.handler, p.into()))
+    }
+}
+
+// a handler and the constraints on its params
 #[derive(Clone, Debug)]
+pub(crate) struct Endpoint<H> {
+    path: String,
+    handler: H,
+    constraints: Vec<(String, Arc<Constraint>)>,
+}
+
+impl<H> Endpoint<H> {
+    // constraints see the decoded values, a malformed one is rejected
+    fn accepts(&self, params: &[(&str, &str)]) -> bool {
+        self.constraints.iter().all(|(name, c)| {
+            params
+                .iter()
+                .filter(|(k, _)| k == name)
+                .all(|(_, v)| decode(v).is_some_and(|v| c.matches(&v)))
+        })
+    }
+}
+
+/// The result of `Router::lookup`.
+///
+/// The `RouterService`s of the `tower` and `hyper` features call the handler
+/// of `Found` and `Head`, and otherwise respond `204 No Content` to `Options`,
+/// with the headers of an accepted `Router::preflight`, `301` or `308` to
+/// `Redirect`, `405 Method Not Allowed` and `404 Not Found`, with an empty body.
+#[derive(Debug, PartialEq)]
+pub enum Match<'a, H> {
+    /// A handler is registered for the method and path.
+    Found(&'a H, Params<'a>),
+    /// A HEAD request answered by the GET handler, the response body should be
+    /// discarded. Only returned when `Router::auto_head` is enabled.
+    Head(&'a H, Params<'a>),
+    /// An OPTIONS request without a handler, answered with the allowed methods.
+    /// Only returned when `Router::auto_options` is enabled.
+    Options(Vec<Method>),
+    /// The path matches after adding or removing the trailing slash, or after
+    /// cleaning it, see `Router::redirect_trailing_slash` and
+    /// `Router::redirect_clean_path`.
+    Redirect(String),
+    /// The path is registered, but only under other methods.
+    MethodNotAllowed(Vec<Method>),
+    /// The path is not registered.
+    NotFound,
+}
+
+impl<'a, H> Match<'a, H> {
+    pub fn is_found(&self) -> bool {
+        matches!(self, Match::Found(..) | Match::Head(..))
+    }
+
+    pub fn found(self) -> Option<(&'a H, Params<'a>)> {
+        match self {
+            Match::Found(h, p) | Match::Head(h, p) => Some((h, p)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct Router<H> {
     path: String,
-    trees: Trees<H>,
-    middleware: Vec<H>,
+    trees: HashMap<Method, Table<H>>,
+    routes: Vec<Route>,
+    middleware: Vec<Arc<dyn Middleware<H>>>,
+    constraints: Vec<(String, Arc<Constraint>)>,
+    auto_head: bool,
+    auto_options: bool,
+    cors: Option<Arc<Cors>>,
+    policies: PathTree<Arc<Cors>>,
+    redirect_trailing_slash: bool,
+    redirect_clean_path: bool,
+    // the path of the outermost resources in a `resources_with` builder
+    parent: Option<String>,
+    // the shallow path of the member a `resources_with` builder is scoped to,
+    // e.g. `/posts/:post_id`
+    member: Option<String>,
+    strict: bool,
+}
+
+impl<H> fmt::Debug for Router<H>
+where
+    H: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Router")
+            .field("path", &self.path)
+            .field("trees", &self.trees)
+            .field("routes", &self.routes)
+            .field("middleware", &self.middleware.len())
+            .field("constraints", &self.constraints)
+            .field("auto_head", &self.auto_head)
+            .field("auto_options", &self.auto_options)
+            .field("cors", &self.cors)
+            .field("policies", &self.policies)
+            .field("redirect_trailing_slash", &self.redirect_trailing_slash)
+            .field("redirect_clean_path", &self.redirect_clean_path)
+            .field("parent", &self.parent)
+            .field("member", &self.member)
+            .field("strict", &self.strict)
+            .finish()
+    }
+}
+
+impl<H> Default for Router<H>
+where
+    H: Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<H> Router<H>
@@ -29,15 +235,70 @@ where
 {
     pub fn new() -> Self {
         Self {
-            trees: Trees::new(),
+            trees: HashMap::new(),
+            routes: Vec::new(),
             path: "/".to_owned(),
             middleware: Vec::new(),
+            constraints: Vec::new(),
+            auto_head: false,
+            auto_options: false,
+            cors: None,
+            policies: PathTree::new(),
+            redirect_trailing_slash: false,
+            redirect_clean_path: false,
+            parent: None,
+            member: None,
+            strict: false,
         }
     }
 
-    // middleware
-    pub fn middleware(&mut self, handler: H) -> &mut Self {
-        self.middleware.push(handler);
+    // middleware, wraps the handlers registered after it
+    pub fn middleware(&mut self, middleware: impl Middleware<H> + 'static) -> &mut Self {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
+
+    // answers HEAD requests with the GET handlers, see `Match::Head`
+    pub fn auto_head(&mut self, enabled: bool) -> &mut Self {
+        self.auto_head = enabled;
+        self
+    }
+
+    // answers OPTIONS requests with the allowed methods, see `Match::Options`
+    pub fn auto_options(&mut self, enabled: bool) -> &mut Self {
+        self.auto_options = enabled;
+        self
+    }
+
+    // CORS policy for the routes registered after it
+    pub fn cors(&mut self, cors: Cors) -> &mut Self {
+        self.cors = Some(Arc::new(cors));
+        self
+    }
+
+    // recommends the path with or without the trailing slash, see `Match::Redirect`
+    pub fn redirect_trailing_slash(&mut self, enabled: bool) -> &mut Self {
+        self.redirect_trailing_slash = enabled;
+        self
+    }
+
+    // recommends the path without `//`, `.` and `..`, see `Match::Redirect`
+    pub fn redirect_clean_path(&mut self, enabled: bool) -> &mut Self {
+        self.redirect_clean_path = enabled;
+        self
+    }
+
+    // strict mode, registering a route that conflicts with a registered one
+    // panics, see `try_handle`
+    pub fn strict(&mut self, enabled: bool) -> &mut Self {
+        self.strict = enabled;
+        self
+    }
+
+    // constrains the params named `name` of the routes registered after it
+    pub fn constraint(&mut self, name: &str, constraint: Constraint) -> &mut Self {
+        self.constraints
+            .push((name.to_owned(), Arc::new(constraint)));
         self
     }
 
@@ -45,11 +306,24 @@ where
     pub fn scope(&mut self, path: &str, builder: impl FnOnce(&mut Router<H>)) -> &mut Self {
         let mut group = Router {
             trees: self.trees.clone(),
+            routes: self.routes.clone(),
             path: join_paths(&self.path, path),
             middleware: self.middleware.clone(),
+            constraints: self.constraints.clone(),
+            auto_head: self.auto_head,
+            auto_options: self.auto_options,
+            cors: self.cors.clone(),
+            policies: self.policies.clone(),
+            redirect_trailing_slash: self.redirect_trailing_slash,
+            redirect_clean_path: self.redirect_clean_path,
+            parent: self.parent.as_ref().map(|p| join_paths(p, path)),
+            member: self.member.as_ref().map(|m| join_paths(m, path)),
+            strict: self.strict,
         };
         builder(&mut group);
         self.trees = group.trees;
+        self.routes = group.routes;
+        self.policies = group.policies;
 
         // let parent_path = self.path.to_owned();
         // self.path = join_paths(&self.path, path);
@@ -60,97 +334,521 @@ where
     }
 
     fn _handle(&mut self, method: Method, path: &str, handler: H) -> &mut Self {
-        // TODO: combine middleware + handler to finally handler
+        if self.strict {
+            if let Err(e) = self.conflict(&method, path) {
+                panic!("{}", e);
+            }
+        }
+        // the first middleware is the outermost layer
+        let handler = self
+            .middleware
+            .iter()
+            .rev()
+            .fold(handler, |next, m| m.wrap(next));
+        let constraints = self
+            .constraints
+            .iter()
+            .filter(|(name, _)| {
+                path.split('/')
+                    .any(|s| s.len() > 1 && s.starts_with(&[':', '*'][..]) && &s[1..] == name)
+            })
+            .cloned()
+            .collect();
         self.trees
-            .entry(method)
-            .or_insert_with(PathTree::new)
-            .insert(path, handler);
+            .entry(method.to_owned())
+            .or_default()
+            .insert(Endpoint {
+                path: path.to_owned(),
+                handler,
+                constraints,
+            });
+        if let Some(cors) = &self.cors {
+            self.policies.insert(path, Arc::clone(cors));
+        }
+        // a re-registered route replaces the earlier one
+        self.routes.retain(|r| r.method != method || r.path != path);
+        self.routes.push(Route {
+            method,
+            path: path.to_owned(),
+            name: None,
+            action: None,
+        });
         self
     }
 
-    pub fn handle(&mut self, method: Method, path: &str, handler: H) -> &mut Self {
-        self._handle(method, &join_paths(&self.path, path), handler)
+    // names the last registered route, all its methods share the name,
+    // routes named before on the same path keep their own
+    pub fn name(&mut self, name: &str) -> &mut Self {
+        if let Some(path) = self.routes.last().map(|r| r.path.to_owned()) {
+            for r in self
+                .routes
+                .iter_mut()
+                .rev()
+                .take_while(|r| r.path == path && r.name.is_none())
+            {
+                r.name = Some(name.to_owned());
+            }
+        }
+        self
+    }
+
+    // builds the path of a named route, `None` if the name or a param is missing
+    pub fn url_for(&self, name: &str, params: &[(&str, &str)]) -> Option<String> {
+        let route = self
+            .routes
+            .iter()
+            .rev()
+            .find(|r| r.name.as_deref() == Some(name))?;
+        let mut url = String::new();
+        for segment in route.path.split('/').skip(1) {
+            url.push('/');
+            match segment.chars().next() {
+                Some(':') | Some('*') => {
+                    let key = &segment[1..];
+                    let (_, value) = params.iter().find(|(k, _)| *k == key)?;
+                    let catch_all = segment.starts_with('*');
+                    url.push_str(&encode(value.trim_start_matches('/'), catch_all));
+                }
+                _ => url.push_str(segment),
+            }
+        }
+        if url.is_empty() {
+            url.push('/');
+        }
+        Some(url)
+    }
+
+    // takes the handler or a shape converted into it, see `IntoRoute`
+    pub fn handle<A, T>(&mut self, method: Method, path: &str, handler: A) -> &mut Self
+    where
+        H: IntoRoute<A, T>,
+    {
+        self._handle(
+            method,
+            &join_paths(&self.path, path),
+            H::into_route(handler),
+        )
+    }
+
+    // like `handle`, but fails if the route conflicts with a registered one,
+    // e.g. `/users/:user_id` and `/users/:id`
+    pub fn try_handle<A, T>(
+        &mut self,
+        method: Method,
+        path: &str,
+        handler: A,
+    ) -> Result<&mut Self, RouterError>
+    where
+        H: IntoRoute<A, T>,
+    {
+        let path = &join_paths(&self.path, path);
+        self.conflict(&method, path)?;
+        Ok(self._handle(method, path, H::into_route(handler)))
+    }
+
+    pub fn try_get<A, T>(&mut self, path: &str, handler: A) -> Result<&mut Self, RouterError>
+    where
+        H: IntoRoute<A, T>,
+    {
+        self.try_handle(Method::GET, path, handler)
+    }
+
+    pub fn try_post<A, T>(&mut self, path: &str, handler: A) -> Result<&mut Self, RouterError>
+    where
+        H: IntoRoute<A, T>,
+    {
+        self.try_handle(Method::POST, path, handler)
     }
 
-    pub fn get(&mut self, path: &str, handler: H) -> &mut Self {
+    pub fn try_delete<A, T>(&mut self, path: &str, handler: A) -> Result<&mut Self, RouterError>
+    where
+        H: IntoRoute<A, T>,
+    {
+        self.try_handle(Method::DELETE, path, handler)
+    }
+
+    pub fn try_patch<A, T>(&mut self, path: &str, handler: A) -> Result<&mut Self, RouterError>
+    where
+        H: IntoRoute<A, T>,
+    {
+        self.try_handle(Method::PATCH, path, handler)
+    }
+
+    pub fn try_put<A, T>(&mut self, path: &str, handler: A) -> Result<&mut Self, RouterError>
+    where
+        H: IntoRoute<A, T>,
+    {
+        self.try_handle(Method::PUT, path, handler)
+    }
+
+    pub fn try_options<A, T>(&mut self, path: &str, handler: A) -> Result<&mut Self, RouterError>
+    where
+        H: IntoRoute<A, T>,
+    {
+        self.try_handle(Method::OPTIONS, path, handler)
+    }
+
+    pub fn try_head<A, T>(&mut self, path: &str, handler: A) -> Result<&mut Self, RouterError>
+    where
+        H: IntoRoute<A, T>,
+    {
+        self.try_handle(Method::HEAD, path, handler)
+    }
+
+    pub fn try_connect<A, T>(&mut self, path: &str, handler: A) -> Result<&mut Self, RouterError>
+    where
+        H: IntoRoute<A, T>,
+    {
+        self.try_handle(Method::CONNECT, path, handler)
+    }
+
+    pub fn try_trace<A, T>(&mut self, path: &str, handler: A) -> Result<&mut Self, RouterError>
+    where
+        H: IntoRoute<A, T>,
+    {
+        self.try_handle(Method::TRACE, path, handler)
+    }
+
+    pub fn get<A, T>(&mut self, path: &str, handler: A) -> &mut Self
+    where
+        H: IntoRoute<A, T>,
+    {
         self.handle(Method::GET, path, handler)
     }
 
-    pub fn post(&mut self, path: &str, handler: H) -> &mut Self {
+    pub fn post<A, T>(&mut self, path: &str, handler: A) -> &mut Self
+    where
+        H: IntoRoute<A, T>,
+    {
         self.handle(Method::POST, path, handler)
     }
 
-    pub fn delete(&mut self, path: &str, handler: H) -> &mut Self {
+    pub fn delete<A, T>(&mut self, path: &str, handler: A) -> &mut Self
+    where
+        H: IntoRoute<A, T>,
+    {
         self.handle(Method::DELETE, path, handler)
     }
 
-    pub fn patch(&mut self, path: &str, handler: H) -> &mut Self {
+    pub fn patch<A, T>(&mut self, path: &str, handler: A) -> &mut Self
+    where
+        H: IntoRoute<A, T>,
+    {
         self.handle(Method::PATCH, path, handler)
     }
 
-    pub fn put(&mut self, path: &str, handler: H) -> &mut Self {
+    pub fn put<A, T>(&mut self, path: &str, handler: A) -> &mut Self
+    where
+        H: IntoRoute<A, T>,
+    {
         self.handle(Method::PUT, path, handler)
     }
 
-    pub fn options(&mut self, path: &str, handler: H) -> &mut Self {
+    pub fn options<A, T>(&mut self, path: &str, handler: A) -> &mut Self
+    where
+        H: IntoRoute<A, T>,
+    {
         self.handle(Method::OPTIONS, path, handler)
     }
 
-    pub fn head(&mut self, path: &str, handler: H) -> &mut Self {
+    pub fn head<A, T>(&mut self, path: &str, handler: A) -> &mut Self
+    where
+        H: IntoRoute<A, T>,
+    {
         self.handle(Method::HEAD, path, handler)
     }
 
-    pub fn connect(&mut self, path: &str, handler: H) -> &mut Self {
+    pub fn connect<A, T>(&mut self, path: &str, handler: A) -> &mut Self
+    where
+        H: IntoRoute<A, T>,
+    {
         self.handle(Method::CONNECT, path, handler)
     }
 
-    pub fn trace(&mut self, path: &str, handler: H) -> &mut Self {
+    pub fn trace<A, T>(&mut self, path: &str, handler: A) -> &mut Self
+    where
+        H: IntoRoute<A, T>,
+    {
         self.handle(Method::TRACE, path, handler)
     }
 
-    pub fn any(&mut self, path: &str, handler: H) -> &mut Self {
+    // every method, OPTIONS is left to `auto_options` when it is enabled so
+    // preflights are answered by the CORS policy
+    pub fn any<A, T>(&mut self, path: &str, handler: A) -> &mut Self
+    where
+        H: IntoRoute<A, T>,
+    {
+        let handler = H::into_route(handler);
         let path = &join_paths(&self.path, path);
-        self._handle(Method::GET, path, handler.to_owned())
-            ._handle(Method::POST, path, handler.to_owned())
-            ._handle(Method::DELETE, path, handler.to_owned())
-            ._handle(Method::PATCH, path, handler.to_owned())
-            ._handle(Method::PUT, path, handler.to_owned())
-            ._handle(Method::OPTIONS, path, handler.to_owned())
-            ._handle(Method::HEAD, path, handler.to_owned())
-            ._handle(Method::CONNECT, path, handler.to_owned())
-            ._handle(Method::TRACE, path, handler.to_owned())
-    }
-
-    pub fn resource(&mut self, path: &str, resource: Vec<((&str, &str, &Method), H)>) -> &mut Self {
-        let path = &join_paths(&self.path, &to_singular(path));
-        for (r, m) in resource.iter() {
-            let new_path = &join_paths(&path, r.1);
-            self._handle(r.2.to_owned(), new_path, m.to_owned());
+        for method in &[
+            Method::GET,
+            Method::POST,
+            Method::DELETE,
+            Method::PATCH,
+            Method::PUT,
+            Method::OPTIONS,
+            Method::HEAD,
+            Method::CONNECT,
+            Method::TRACE,
+        ] {
+            if *method == Method::OPTIONS && self.auto_options {
+                continue;
+            }
+            self._handle(method.to_owned(), path, handler.to_owned());
+        }
+        self
+    }
+
+    pub fn resource(
+        &mut self,
+        name: impl Into<ResourceName>,
+        resource: Vec<ResourceRoute<H>>,
+    ) -> &mut Self {
+        let path = &join_paths(&self.path, &name.into().resource_path());
+        for r in resource {
+            let new_path = &join_paths(path, &r.path);
+            self._handle(r.method, new_path, r.handler)
+                ._action(r.action);
         }
         self
     }
 
     pub fn resources(
         &mut self,
-        path: &str,
-        resources: Vec<((&str, &str, &Method), H)>,
+        name: impl Into<ResourceName>,
+        resources: Vec<ResourceRoute<H>>,
     ) -> &mut Self {
-        let path = &join_paths(&self.path, &to_plural(path));
-        for (r, m) in resources.iter() {
-            let new_path = &join_paths(&path, &r.1.replace("id", &(to_singular(path) + "_id")));
-            self._handle(r.2.to_owned(), new_path, m.to_owned());
+        let name = name.into();
+        let path = &join_paths(&self.path, &name.resources_path());
+        // shallow members skip the parent resources, shallow collections keep
+        // only the member of the immediate parent
+        let shallow_member = &join_paths(
+            self.parent.as_ref().unwrap_or(&self.path),
+            &name.resources_path(),
+        );
+        let shallow_collection = &join_paths(
+            self.member.as_ref().unwrap_or(&self.path),
+            &name.resources_path(),
+        );
+        let id = &(":".to_owned() + &name.resources_param());
+        for r in resources {
+            let path = match (r.shallow, r.path.starts_with(":id")) {
+                (false, _) => path,
+                (true, true) => shallow_member,
+                (true, false) => shallow_collection,
+            };
+            let new_path = &join_paths(path, &r.path.replace(":id", id));
+            self._handle(r.method, new_path, r.handler)
+                ._action(r.action);
         }
         self
     }
 
-    pub fn find<'a>(
-        &'a self,
-        method: &'a Method,
-        path: &'a str,
-    ) -> Option<(&'a H, Vec<(&'a str, &'a str)>)> {
-        let tree = self.trees.get(method)?;
-        tree.find(path)
+    // resources with nested routes, the builder is scoped to a member of it,
+    // e.g. `/users/:user_id`
+    pub fn resources_with(
+        &mut self,
+        name: impl Into<ResourceName>,
+        resources: Vec<ResourceRoute<H>>,
+        builder: impl FnOnce(&mut Router<H>),
+    ) -> &mut Self {
+        let name = name.into();
+        let member = join_paths(
+            &name.resources_path(),
+            &(":".to_owned() + &name.resources_param()),
+        );
+        let parent = self.parent.clone().unwrap_or_else(|| self.path.clone());
+        let shallow = join_paths(&parent, &member);
+        self.resources(name, resources).scope(&member, |r| {
+            r.parent = Some(parent);
+            r.member = Some(shallow);
+            builder(r)
+        })
+    }
+
+    // the registered route matching the same requests, params match any name
+    fn conflict(&self, method: &Method, path: &str) -> Result<(), RouterError> {
+        let pattern = |path: &str| -> Vec<String> {
+            path.split('/')
+                .map(|s| match s.chars().next() {
+                    Some(c @ ':') | Some(c @ '*') => c.to_string(),
+                    _ => s.to_owned(),
+                })
+                .collect()
+        };
+        let new = pattern(path);
+        match self
+            .routes
+            .iter()
+            .find(|r| r.method == *method && pattern(&r.path) == new)
+        {
+            Some(r) => Err(RouterError::Conflict {
+                method: method.to_owned(),
+                path: path.to_owned(),
+                existing: r.path.to_owned(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    fn _action(&mut self, action: &str) -> &mut Self {
+        if let Some(r) = self.routes.last_mut() {
+            r.action = Some(action.to_owned());
+        }
+        self
+    }
+
+    // registered routes, in registration order
+    pub fn routes(&self) -> impl Iterator<Item = &Route> {
+        self.routes.iter()
+    }
+
+    // formats the registered routes as a table
+    pub fn table(&self) -> RouteTable<'_> {
+        RouteTable(&self.routes)
+    }
+
+    pub fn find<'a>(&'a self, method: &'a Method, path: &'a str) -> Option<(&'a H, Params<'a>)> {
+        self.trees.get(method)?.find(path)
+    }
+
+    // like `find`, but tells a missing path apart from a missing method
+    pub fn lookup<'a>(&'a self, method: &'a Method, path: &'a str) -> Match<'a, H> {
+        if let Some((h, p)) = self.find(method, path) {
+            return Match::Found(h, p);
+        }
+        if self.auto_head && method == Method::HEAD {
+            if let Some((h, p)) = self.find(&Method::GET, path) {
+                return Match::Head(h, p);
+            }
+        }
+        let allowed = self.allowed(path);
+        if allowed.is_empty() {
+            self.redirect(method, path)
+                .map_or(Match::NotFound, Match::Redirect)
+        } else if self.auto_options && method == Method::OPTIONS {
+            Match::Options(allowed)
+        } else {
+            Match::MethodNotAllowed(allowed)
+        }
+    }
+
+    fn redirect(&self, method: &Method, path: &str) -> Option<String> {
+        let matches = |p: &str| self.allowed(p).contains(method);
+        let toggle = |p: &str| {
+            if p.ends_with('/') {
+                p.trim_end_matches('/').to_owned()
+            } else {
+                p.to_owned() + "/"
+            }
+        };
+
+        if self.redirect_clean_path {
+            let cleaned = clean_path(path);
+            if cleaned != path {
+                if matches(&cleaned) {
+                    return Some(cleaned);
+                }
+                if self.redirect_trailing_slash && matches(&toggle(&cleaned)) {
+                    return Some(toggle(&cleaned));
+                }
+            }
+        }
+        if self.redirect_trailing_slash && path != "/" && matches(&toggle(path)) {
+            return Some(toggle(path));
+        }
+        None
+    }
+
+    // checks a CORS preflight request against the policy of the path
+    pub fn preflight(
+        &self,
+        path: &str,
+        origin: &str,
+        method: &Method,
+        headers: &[&str],
+    ) -> Option<Preflight> {
+        let (cors, _) = self.policies.find(path)?;
+        cors.preflight(origin, method, headers, self.allowed(path))
+    }
+
+    // methods registered for the path, sorted by name
+    pub fn allowed(&self, path: &str) -> Vec<Method> {
+        let mut methods: Vec<Method> = self
+            .trees
+            .iter()
+            .filter(|(m, _)| self.find(m, path).is_some())
+            .map(|(m, _)| m.to_owned())
+            .collect();
+        if self.auto_head && methods.contains(&Method::GET) && !methods.contains(&Method::HEAD) {
+            methods.push(Method::HEAD);
+        }
+        if self.auto_options && !methods.is_empty() && !methods.contains(&Method::OPTIONS) {
+            methods.push(Method::OPTIONS);
+        }
+        methods.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        methods
+    }
+}
+
+// the params of the path if the pattern matches it, like the tree a param
+// matches a segment and a catch-all the rest
+fn captures<'a>(pattern: &'a str, path: &'a str) -> Option<Vec<(&'a str, &'a str)>> {
+    let mut params = Vec::new();
+    let mut segments = path.split('/');
+    // the start of the next segment
+    let mut offset = 0;
+    for s in pattern.split('/') {
+        if let Some(name) = s.strip_prefix('*') {
+            let rest = path.get(offset..).filter(|r| !r.is_empty())?;
+            params.push((name, rest));
+            return Some(params);
+        }
+        let v = segments.next()?;
+        offset += v.len() + 1;
+        match s.strip_prefix(':') {
+            Some(name) if !v.is_empty() => params.push((name, v)),
+            None if s == v => {}
+            _ => return None,
+        }
+    }
+    match segments.next() {
+        Some(_) => None,
+        None => Some(params),
+    }
+}
+
+// orders matching routes like the tree, static segments before params before
+// catch-alls
+fn rank(pattern: &str) -> Vec<u8> {
+    pattern
+        .split('/')
+        .map(|s| match s.chars().next() {
+            Some(':') => 1,
+            Some('*') => 2,
+            _ => 0,
+        })
+        .collect()
+}
+
+// removes empty, `.` and `..` segments, keeping the trailing slash
+fn clean_path(path: &str) -> String {
+    let mut segments = Vec::new();
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            _ => segments.push(segment),
+        }
+    }
+    let mut cleaned = "/".to_owned() + &segments.join("/");
+    let trailing = path.ends_with('/') || path.ends_with("/.") || path.ends_with("/..");
+    if trailing && cleaned != "/" {
+        cleaned.push('/');
     }
+    cleaned
 }
 
 fn join_paths(a: &str, mut b: &str) -> String {
@@ -232,4 +930,511 @@ mod tests {
         let r = router.find(&Method::OPTIONS, "/admin");
         assert!(r.is_none());
     }
+
+    #[test]
+    fn middleware() {
+        type F = Arc<dyn Fn(&mut Vec<&'static str>) -> usize + Send + Sync>;
+        let mut router = Router::<F>::new();
+
+        router
+            .middleware(|next: F| -> F {
+                Arc::new(move |log| {
+                    log.push("m1 before");
+                    let r = next(log);
+                    log.push("m1 after");
+                    r
+                })
+            })
+            .get(
+                "/foo",
+                Arc::new(|log| {
+                    log.push("foo");
+                    1
+                }),
+            )
+            .scope("/admin", |a| {
+                a.middleware(|next: F| -> F {
+                    Arc::new(move |log| {
+                        log.push("m2 before");
+                        let r = next(log);
+                        log.push("m2 after");
+                        r
+                    })
+                })
+                .get(
+                    "/bar",
+                    Arc::new(|log| {
+                        log.push("bar");
+                        2
+                    }),
+                )
+                .scope("/auth", |a| {
+                    // short-circuits the chain
+                    a.middleware(|_: F| -> F {
+                        Arc::new(|log| {
+                            log.push("denied");
+                            0
+                        })
+                    })
+                    .get(
+                        "/baz",
+                        Arc::new(|log| {
+                            log.push("baz");
+                            3
+                        }),
+                    );
+                });
+            })
+            .get(
+                "/qux",
+                Arc::new(|log| {
+                    log.push("qux");
+                    4
+                }),
+            );
+
+        let mut log = Vec::new();
+        let (h, _) = router.find(&Method::GET, "/foo").unwrap();
+        assert_eq!(h(&mut log), 1);
+        assert_eq!(log, ["m1 before", "foo", "m1 after"]);
+
+        let mut log = Vec::new();
+        let (h, _) = router.find(&Method::GET, "/admin/bar").unwrap();
+        assert_eq!(h(&mut log), 2);
+        assert_eq!(
+            log,
+            ["m1 before", "m2 before", "bar", "m2 after", "m1 after"]
+        );
+
+        let mut log = Vec::new();
+        let (h, _) = router.find(&Method::GET, "/admin/auth/baz").unwrap();
+        assert_eq!(h(&mut log), 0);
+        assert_eq!(
+            log,
+            ["m1 before", "m2 before", "denied", "m2 after", "m1 after"]
+        );
+
+        // middleware registered in a scope does not leak out of it
+        let mut log = Vec::new();
+        let (h, _) = router.find(&Method::GET, "/qux").unwrap();
+        assert_eq!(h(&mut log), 4);
+        assert_eq!(log, ["m1 before", "qux", "m1 after"]);
+    }
+
+    #[test]
+    fn lookup() {
+        type F = fn() -> usize;
+        let mut router = Router::<F>::new();
+
+        router
+            .get("/users/:id", || 0)
+            .put("/users/:id", || 1)
+            .delete("/users/:id", || 2)
+            .post("/users", || 3);
+
+        let r = router.lookup(&Method::GET, "/users/1");
+        assert!(r.is_found());
+        let (h, p) = r.found().unwrap();
+        assert_eq!(h(), 0);
+        assert_eq!(p, [("id", "1")]);
+
+        assert_eq!(
+            router.lookup(&Method::POST, "/users/1"),
+            Match::MethodNotAllowed(vec![Method::DELETE, Method::GET, Method::PUT])
+        );
+        assert_eq!(
+            router.lookup(&Method::GET, "/users"),
+            Match::MethodNotAllowed(vec![Method::POST])
+        );
+        assert_eq!(router.lookup(&Method::GET, "/posts"), Match::NotFound);
+        assert!(router.allowed("/posts").is_empty());
+    }
+
+    #[test]
+    fn url_for() {
+        type F = fn() -> usize;
+        let mut router = Router::<F>::new();
+
+        router
+            .get("/", || 0)
+            .name("root")
+            .scope("/v1", |v1| {
+                v1.get("/login", || 1).name("login").scope("/users", |u| {
+                    u.any("/:user_id/repos/:id", || 2).name("user_repo");
+                });
+            })
+            .get("/static/*path", || 3)
+            .name("static")
+            .post("/bar", || 4);
+
+        assert_eq!(router.url_for("root", &[]), Some("/".to_owned()));
+        assert_eq!(router.url_for("login", &[]), Some("/v1/login".to_owned()));
+        assert_eq!(
+            router.url_for("user_repo", &[("id", "2"), ("user_id", "1")]),
+            Some("/v1/users/1/repos/2".to_owned())
+        );
+        assert_eq!(
+            router.url_for("user_repo", &[("id", "a b"), ("user_id", "Jürgen")]),
+            Some("/v1/users/J%C3%BCrgen/repos/a%20b".to_owned())
+        );
+        assert_eq!(
+            router.url_for("static", &[("path", "css/app.css")]),
+            Some("/static/css/app.css".to_owned())
+        );
+        assert_eq!(router.url_for("user_repo", &[("user_id", "1")]), None);
+        assert_eq!(router.url_for("bar", &[]), None);
+
+        let mut router = Router::<F>::new();
+        router
+            .get("/users", || 0)
+            .name("users")
+            .post("/users", || 1)
+            .name("create_user");
+
+        assert_eq!(router.url_for("users", &[]), Some("/users".to_owned()));
+        assert_eq!(
+            router.url_for("create_user", &[]),
+            Some("/users".to_owned())
+        );
+        let names: Vec<_> = router.routes().map(|r| r.name()).collect();
+        assert_eq!(names, [Some("users"), Some("create_user")]);
+    }
+
+    #[test]
+    fn routes() {
+        type F = fn() -> usize;
+        let mut router = Router::<F>::new();
+
+        router
+            .scope("/v1", |v1| {
+                v1.get("/login", || 0).name("login");
+            })
+            .post("/users/:user_id", || 1)
+            .post("/users/:user_id", || 2);
+
+        let routes: Vec<_> = router
+            .routes()
+            .map(|r| (r.method().to_owned(), r.path(), r.name(), r.action()))
+            .collect();
+        assert_eq!(
+            routes,
+            [
+                (Method::GET, "/v1/login", Some("login"), None),
+                (Method::POST, "/users/:user_id", None, None),
+            ]
+        );
+
+        assert_eq!(
+            router.table().to_string(),
+            " Name Method Path            Action\n\
+             login GET    /v1/login\n\
+             \x20     POST   /users/:user_id\n"
+        );
+        let (handler, _) = router.find(&Method::POST, "/users/1").unwrap();
+        assert_eq!(handler(), 2);
+    }
+
+    #[test]
+    fn auto_head() {
+        type F = fn() -> usize;
+        let mut router = Router::<F>::new();
+
+        router
+            .get("/users", || 0)
+            .head("/posts", || 1)
+            .get("/posts", || 2)
+            .post("/login", || 3);
+
+        assert!(router.find(&Method::HEAD, "/users").is_none());
+        assert_eq!(
+            router.lookup(&Method::HEAD, "/users"),
+            Match::MethodNotAllowed(vec![Method::GET])
+        );
+
+        router.auto_head(true);
+
+        match router.lookup(&Method::HEAD, "/users") {
+            Match::Head(h, p) => {
+                assert_eq!(h(), 0);
+                assert_eq!(p, []);
+            }
+            _ => panic!("expected a HEAD match"),
+        }
+        // an explicit HEAD handler wins
+        match router.lookup(&Method::HEAD, "/posts") {
+            Match::Found(h, _) => assert_eq!(h(), 1),
+            _ => panic!("expected the HEAD handler"),
+        }
+        assert_eq!(
+            router.lookup(&Method::HEAD, "/login"),
+            Match::MethodNotAllowed(vec![Method::POST])
+        );
+        assert_eq!(
+            router.lookup(&Method::DELETE, "/users"),
+            Match::MethodNotAllowed(vec![Method::GET, Method::HEAD])
+        );
+    }
+
+    #[test]
+    fn auto_options() {
+        type F = fn() -> usize;
+        let mut router = Router::<F>::new();
+
+        router
+            .get("/users", || 0)
+            .post("/users", || 1)
+            .options("/posts", || 2)
+            .get("/posts", || 3)
+            .scope("/api", |api| {
+                api.cors(
+                    Cors::new()
+                        .origin("https://example.com")
+                        .header("Content-Type")
+                        .max_age(600),
+                )
+                .get("/items", || 4)
+                .delete("/items/:id", || 5);
+            })
+            .get("/public", || 6);
+
+        assert_eq!(
+            router.lookup(&Method::OPTIONS, "/users"),
+            Match::MethodNotAllowed(vec![Method::GET, Method::POST])
+        );
+
+        router.auto_options(true);
+
+        assert_eq!(
+            router.lookup(&Method::OPTIONS, "/users"),
+            Match::Options(vec![Method::GET, Method::OPTIONS, Method::POST])
+        );
+        assert_eq!(
+            router.lookup(&Method::PUT, "/users"),
+            Match::MethodNotAllowed(vec![Method::GET, Method::OPTIONS, Method::POST])
+        );
+        // an explicit OPTIONS handler wins
+        assert!(router.lookup(&Method::OPTIONS, "/posts").is_found());
+        // `any` leaves OPTIONS to `auto_options`
+        router.any("/any", || 7);
+        assert_eq!(
+            router.lookup(&Method::OPTIONS, "/any"),
+            Match::Options(vec![
+                Method::CONNECT,
+                Method::DELETE,
+                Method::GET,
+                Method::HEAD,
+                Method::OPTIONS,
+                Method::PATCH,
+                Method::POST,
+                Method::PUT,
+                Method::TRACE,
+            ])
+        );
+        assert_eq!(router.lookup(&Method::OPTIONS, "/nope"), Match::NotFound);
+
+        let p = router
+            .preflight(
+                "/api/items/1",
+                "https://example.com",
+                &Method::DELETE,
+                &["content-type"],
+            )
+            .unwrap();
+        assert_eq!(p.methods, [Method::DELETE, Method::OPTIONS]);
+        assert_eq!(
+            p.headers(),
+            [
+                (
+                    "access-control-allow-origin",
+                    "https://example.com".to_owned()
+                ),
+                ("access-control-allow-methods", "DELETE, OPTIONS".to_owned()),
+                ("access-control-allow-headers", "content-type".to_owned()),
+                ("access-control-max-age", "600".to_owned()),
+                ("vary", "origin".to_owned()),
+            ]
+        );
+
+        // wrong origin, unregistered method, unknown header, no policy
+        assert!(router
+            .preflight("/api/items", "https://evil.com", &Method::GET, &[])
+            .is_none());
+        assert!(router
+            .preflight("/api/items", "https://example.com", &Method::POST, &[])
+            .is_none());
+        assert!(router
+            .preflight(
+                "/api/items",
+                "https://example.com",
+                &Method::GET,
+                &["x-token"]
+            )
+            .is_none());
+        assert!(router
+            .preflight("/public", "https://example.com", &Method::GET, &[])
+            .is_none());
+    }
+
+    #[test]
+    fn redirect() {
+        type F = fn() -> usize;
+        let mut router = Router::<F>::new();
+
+        router
+            .get("/users", || 0)
+            .get("/users/:id/posts", || 1)
+            .scope("admin", |a| {
+                a.any("/", || 2);
+            });
+
+        assert_eq!(router.lookup(&Method::GET, "/admin"), Match::NotFound);
+
+        router.redirect_trailing_slash(true);
+
+        assert_eq!(
+            router.lookup(&Method::GET, "/admin"),
+            Match::Redirect("/admin/".to_owned())
+        );
+        assert_eq!(
+            router.lookup(&Method::GET, "/users/"),
+            Match::Redirect("/users".to_owned())
+        );
+        assert_eq!(router.lookup(&Method::POST, "/users/"), Match::NotFound);
+        assert_eq!(router.lookup(&Method::GET, "//users"), Match::NotFound);
+
+        router.redirect_clean_path(true);
+
+        assert_eq!(
+            router.lookup(&Method::GET, "//users/./1/../2/posts"),
+            Match::Redirect("/users/2/posts".to_owned())
+        );
+        assert_eq!(
+            router.lookup(&Method::GET, "/users//2/posts/"),
+            Match::Redirect("/users/2/posts".to_owned())
+        );
+        assert_eq!(
+            router.lookup(&Method::GET, "/../admin"),
+            Match::Redirect("/admin/".to_owned())
+        );
+
+        assert_eq!(clean_path("/"), "/");
+        assert_eq!(clean_path("/a/b/.."), "/a/");
+        assert_eq!(clean_path("/a/../../b"), "/b");
+        assert_eq!(clean_path("a//b/"), "/a/b/");
+    }
+
+    #[test]
+    fn constraint() {
+        type F = fn() -> usize;
+        let mut router = Router::<F>::new();
+
+        router
+            .get("/posts/:id", || 0)
+            .constraint("id", Constraint::Digits)
+            .get("/users/:id", || 1)
+            .delete("/users/:id", || 2)
+            .scope("/tags", |t| {
+                t.constraint("tag", Constraint::Slug)
+                    .get("/:tag", || 3)
+                    .get("/:tag/:id", || 4);
+            })
+            .get("/pages/:tag", || 5);
+
+        assert!(router.find(&Method::GET, "/posts/new").is_some());
+        assert!(router.find(&Method::GET, "/users/42").is_some());
+        assert!(router.find(&Method::GET, "/users/new").is_none());
+        assert_eq!(router.lookup(&Method::POST, "/users/new"), Match::NotFound);
+        assert_eq!(
+            router.lookup(&Method::POST, "/users/42"),
+            Match::MethodNotAllowed(vec![Method::DELETE, Method::GET])
+        );
+
+        assert!(router.find(&Method::GET, "/tags/rust-lang").is_some());
+        assert!(router.find(&Method::GET, "/tags/Rust").is_none());
+        assert!(router.find(&Method::GET, "/tags/rust/42").is_some());
+        assert!(router.find(&Method::GET, "/tags/rust/x").is_none());
+        // constraints registered in a scope do not leak out of it
+        assert!(router.find(&Method::GET, "/pages/Rust").is_some());
+        // constraints see the decoded value
+        assert!(router.find(&Method::GET, "/users/%34%32").is_some());
+        assert!(router.find(&Method::GET, "/users/%34%2F").is_none());
+    }
+
+    #[test]
+    fn constraint_fallthrough() {
+        type F = fn() -> usize;
+        let mut router = Router::<F>::new();
+
+        router
+            .get("/files/new", || 0)
+            .get("/files/*path", || 1)
+            .constraint("id", Constraint::Digits)
+            .get("/files/:id", || 2)
+            .get("/files/:id/raw", || 3)
+            .get("/users/:id", || 4);
+
+        let find = |path| router.find(&Method::GET, path).map(|(h, p)| (h(), p));
+        assert_eq!(find("/files/new").unwrap().0, 0);
+        assert_eq!(find("/files/42").unwrap().0, 2);
+        let (h, p) = find("/files/abc").unwrap();
+        assert_eq!(h, 1);
+        assert_eq!(p, [("path", "abc")]);
+        assert_eq!(find("/files/42/raw").unwrap().0, 3);
+        let (h, p) = find("/files/abc/raw").unwrap();
+        assert_eq!(h, 1);
+        assert_eq!(p, [("path", "abc/raw")]);
+        assert!(find("/users/abc").is_none());
+        assert!(router.lookup(&Method::GET, "/files/abc").is_found());
+    }
+
+    #[test]
+    fn conflict() {
+        type F = fn() -> usize;
+        let mut router = Router::<F>::new();
+
+        router
+            .try_get("/users/:id", || 0)
+            .unwrap()
+            .try_get("/users/new", || 1)
+            .unwrap()
+            .try_post("/users/:user_id", || 2)
+            .unwrap()
+            .try_get("/users/:user_id/posts", || 3)
+            .unwrap();
+
+        let err = router.try_get("/users/:user_id", || 4).unwrap_err();
+        assert_eq!(
+            err,
+            RouterError::Conflict {
+                method: Method::GET,
+                path: "/users/:user_id".to_owned(),
+                existing: "/users/:id".to_owned(),
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "route `GET /users/:user_id` conflicts with `GET /users/:id`"
+        );
+        assert!(router.try_get("/users/new", || 5).is_err());
+        router.scope("/users", |users| {
+            assert!(users.try_get("/:id", || 6).is_err());
+        });
+
+        // the failed registrations are not added
+        let (h, _) = router.find(&Method::GET, "/users/1").unwrap();
+        assert_eq!(h(), 0);
+        assert_eq!(router.routes().count(), 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "route `GET /static/*file` conflicts with `GET /static/*path`")]
+    fn strict() {
+        type F = fn() -> usize;
+        let mut router = Router::<F>::new();
+
+        router.strict(true).get("/static/*path", || 0);
+        router.scope("/static", |s| {
+            s.get("/*file", || 1);
+        });
+    }
 }
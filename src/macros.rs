@@ -0,0 +1,15 @@
+/// A `Pattern` validated at compile time, a malformed pattern fails the build.
+///
+/// ```compile_fail
+/// # #[macro_use] extern crate trek_router;
+/// # fn main() {
+/// let pattern = route!("/users/:id/posts/:id");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! route {
+    ($pattern:expr) => {{
+        const PATTERN: $crate::Pattern<'static> = $crate::Pattern::new($pattern);
+        PATTERN
+    }};
+}
@@ -0,0 +1,24 @@
+/// Wraps a handler into a new handler.
+///
+/// Middleware registered on a `Router` wraps every handler registered afterwards,
+/// including handlers registered in nested `scope`s. The first registered
+/// middleware is the outermost layer, so for `m1` then `m2` the final handler
+/// is `m1.wrap(m2.wrap(handler))`:
+///
+/// ```text
+/// m1 before -> m2 before -> handler -> m2 after -> m1 after
+/// ```
+///
+/// A middleware short-circuits the chain by returning without calling `next`.
+pub trait Middleware<H>: Send + Sync {
+    fn wrap(&self, next: H) -> H;
+}
+
+impl<H, F> Middleware<H> for F
+where
+    F: Fn(H) -> H + Send + Sync + 'static,
+{
+    fn wrap(&self, next: H) -> H {
+        self(next)
+    }
+}
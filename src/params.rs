@@ -0,0 +1,457 @@
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// Route parameters captured by `Router::find`, in declaration order.
+///
+/// Values are percent-decoded, the raw form is kept and available via `raw`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Params<'a>(Vec<Param<'a>>);
+
+#[derive(Clone, Debug, PartialEq)]
+struct Param<'a> {
+    name: Cow<'a, str>,
+    raw: Cow<'a, str>,
+    // `None` if the raw value is not valid percent-encoded UTF-8
+    value: Option<Cow<'a, str>>,
+}
+
+impl<'a> Param<'a> {
+    fn value(&self) -> &str {
+        self.value.as_deref().unwrap_or(&self.raw)
+    }
+}
+
+impl<'a> Params<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn find(&self, name: &str) -> Option<&Param<'a>> {
+        self.0.iter().find(|p| p.name == name)
+    }
+
+    /// The decoded value of the first parameter named `name`, `None` if it is
+    /// missing or can not be decoded, see `try_get`.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.find(name)?.value.as_deref()
+    }
+
+    /// The raw value of the first parameter named `name`, as captured from the path.
+    pub fn raw(&self, name: &str) -> Option<&str> {
+        self.find(name).map(|p| &*p.raw)
+    }
+
+    /// Like `get`, but tells a missing parameter apart from an undecodable one.
+    pub fn try_get(&self, name: &str) -> Result<&str, ParamError> {
+        let param = self
+            .find(name)
+            .ok_or_else(|| ParamError::Missing(name.to_owned()))?;
+        param.value.as_deref().ok_or_else(|| ParamError::Decode {
+            name: name.to_owned(),
+            value: param.raw.to_string(),
+        })
+    }
+
+    /// Parses the decoded value of the parameter named `name`.
+    pub fn parse<T>(&self, name: &str) -> Result<T, ParamError>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        let value = self.try_get(name)?;
+        value.parse().map_err(|e: T::Err| ParamError::Invalid {
+            name: name.to_owned(),
+            value: value.to_owned(),
+            message: e.to_string(),
+        })
+    }
+
+    /// The names and decoded values, an undecodable value is yielded raw.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0.iter().map(|p| (&*p.name, p.value()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Copies the borrowed names and values, e.g. for storing the parameters
+    /// in request extensions.
+    pub fn into_owned(self) -> Params<'static> {
+        Params(
+            self.0
+                .into_iter()
+                .map(|p| Param {
+                    name: Cow::Owned(p.name.into_owned()),
+                    raw: Cow::Owned(p.raw.into_owned()),
+                    value: p.value.map(|v| Cow::Owned(v.into_owned())),
+                })
+                .collect(),
+        )
+    }
+
+    /// Deserializes the decoded parameters into a struct or map, values are
+    /// parsed from their string form.
+    #[cfg(feature = "serde")]
+    pub fn deserialize<T>(&'a self) -> Result<T, ParamError>
+    where
+        T: serde::Deserialize<'a>,
+    {
+        T::deserialize(de::ParamsDeserializer(self.0.iter()))
+    }
+}
+
+impl<'a> From<Vec<(&'a str, &'a str)>> for Params<'a> {
+    fn from(params: Vec<(&'a str, &'a str)>) -> Self {
+        Params(
+            params
+                .into_iter()
+                .map(|(name, raw)| Param {
+                    name: Cow::Borrowed(name),
+                    raw: Cow::Borrowed(raw),
+                    value: decode(raw),
+                })
+                .collect(),
+        )
+    }
+}
+
+impl<'a> IntoIterator for &'a Params<'a> {
+    type Item = (&'a str, &'a str);
+    type IntoIter = Box<dyn Iterator<Item = (&'a str, &'a str)> + 'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Box::new(self.iter())
+    }
+}
+
+impl<'a, 'b, const N: usize> PartialEq<[(&'b str, &'b str); N]> for Params<'a> {
+    fn eq(&self, other: &[(&'b str, &'b str); N]) -> bool {
+        self.len() == N && self.iter().zip(other.iter()).all(|(a, b)| a == *b)
+    }
+}
+
+// percent-decodes a path segment, `None` on a malformed escape or invalid UTF-8
+pub(crate) fn decode(raw: &str) -> Option<Cow<'_, str>> {
+    if !raw.contains('%') {
+        return Some(Cow::Borrowed(raw));
+    }
+    let bytes = raw.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            // `from_str_radix` alone accepts a sign, e.g. `%+1`
+            let hex = raw.get(i + 1..i + 3)?;
+            if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return None;
+            }
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok().map(Cow::Owned)
+}
+
+// percent-encodes a value for a path, keeping `/` when `slash` is set
+pub(crate) fn encode(value: &str, slash: bool) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for b in value.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' => encoded.push(b as char),
+            b'-' | b'.' | b'_' | b'~' | b'!' | b'$' | b'&' | b'\'' | b'(' | b')' | b'*' | b'+'
+            | b',' | b';' | b'=' | b':' | b'@' => encoded.push(b as char),
+            b'/' if slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    encoded
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParamError {
+    /// No parameter with the name.
+    Missing(String),
+    /// The value is not valid percent-encoded UTF-8.
+    Decode { name: String, value: String },
+    /// The value could not be parsed.
+    Invalid {
+        name: String,
+        value: String,
+        message: String,
+    },
+    /// The parameters could not be deserialized.
+    Deserialize(String),
+}
+
+impl fmt::Display for ParamError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParamError::Missing(name) => write!(f, "missing param `{}`", name),
+            ParamError::Decode { name, value } => {
+                write!(
+                    f,
+                    "param `{}` = `{}` is not valid percent-encoded UTF-8",
+                    name, value
+                )
+            }
+            ParamError::Invalid {
+                name,
+                value,
+                message,
+            } => write!(f, "invalid param `{}` = `{}`: {}", name, value, message),
+            ParamError::Deserialize(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl Error for ParamError {}
+
+#[cfg(feature = "serde")]
+mod de {
+    use super::{Param, ParamError};
+    use serde::de::value::{BorrowedStrDeserializer, MapDeserializer};
+    use serde::de::{self, Deserializer, IntoDeserializer, Visitor};
+    use serde::forward_to_deserialize_any;
+    use std::slice::Iter;
+
+    impl de::Error for ParamError {
+        fn custom<T: std::fmt::Display>(msg: T) -> Self {
+            ParamError::Deserialize(msg.to_string())
+        }
+    }
+
+    pub(super) struct ParamsDeserializer<'de>(pub(super) Iter<'de, Param<'de>>);
+
+    impl<'de> Deserializer<'de> for ParamsDeserializer<'de> {
+        type Error = ParamError;
+
+        fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+            let entries = self
+                .0
+                .map(|p| (BorrowedStrDeserializer::new(&p.name), Value(p)));
+            visitor.visit_map(MapDeserializer::new(entries))
+        }
+
+        forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+            bytes byte_buf option unit unit_struct newtype_struct seq tuple
+            tuple_struct map struct enum identifier ignored_any
+        }
+    }
+
+    /// A decoded param value, parsed into the requested type.
+    struct Value<'de>(&'de Param<'de>);
+
+    impl<'de> Value<'de> {
+        fn decoded(&self) -> Result<&'de str, ParamError> {
+            match &self.0.value {
+                Some(v) => Ok(v),
+                None => Err(ParamError::Decode {
+                    name: self.0.name.to_string(),
+                    value: self.0.raw.to_string(),
+                }),
+            }
+        }
+    }
+
+    macro_rules! parse_value {
+        ($($method:ident => $visit:ident,)*) => {
+            $(
+                fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+                    let value = self.decoded()?;
+                    match value.parse() {
+                        Ok(v) => visitor.$visit(v),
+                        Err(e) => Err(de::Error::custom(format!("`{}`: {}", value, e))),
+                    }
+                }
+            )*
+        };
+    }
+
+    impl<'de> Deserializer<'de> for Value<'de> {
+        type Error = ParamError;
+
+        fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+            visitor.visit_borrowed_str(self.decoded()?)
+        }
+
+        fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+            visitor.visit_some(self)
+        }
+
+        fn deserialize_newtype_struct<V: Visitor<'de>>(
+            self,
+            _: &'static str,
+            visitor: V,
+        ) -> Result<V::Value, Self::Error> {
+            visitor.visit_newtype_struct(self)
+        }
+
+        fn deserialize_enum<V: Visitor<'de>>(
+            self,
+            _: &'static str,
+            _: &'static [&'static str],
+            visitor: V,
+        ) -> Result<V::Value, Self::Error> {
+            visitor.visit_enum(BorrowedStrDeserializer::new(self.decoded()?))
+        }
+
+        parse_value! {
+            deserialize_bool => visit_bool,
+            deserialize_i8 => visit_i8,
+            deserialize_i16 => visit_i16,
+            deserialize_i32 => visit_i32,
+            deserialize_i64 => visit_i64,
+            deserialize_i128 => visit_i128,
+            deserialize_u8 => visit_u8,
+            deserialize_u16 => visit_u16,
+            deserialize_u32 => visit_u32,
+            deserialize_u64 => visit_u64,
+            deserialize_u128 => visit_u128,
+            deserialize_f32 => visit_f32,
+            deserialize_f64 => visit_f64,
+            deserialize_char => visit_char,
+        }
+
+        forward_to_deserialize_any! {
+            str string bytes byte_buf unit unit_struct seq tuple tuple_struct map
+            struct identifier ignored_any
+        }
+    }
+
+    impl<'de> IntoDeserializer<'de, ParamError> for Value<'de> {
+        type Deserializer = Self;
+
+        fn into_deserializer(self) -> Self {
+            self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn params() {
+        let params = Params::from(vec![("user_id", "42"), ("name", "trek"), ("user_id", "7")]);
+
+        assert_eq!(params.len(), 3);
+        assert_eq!(params.get("user_id"), Some("42"));
+        assert_eq!(params.get("name"), Some("trek"));
+        assert_eq!(params.get("id"), None);
+        assert_eq!(
+            params.iter().map(|(k, _)| k).collect::<Vec<_>>(),
+            ["user_id", "name", "user_id"]
+        );
+
+        assert_eq!(params.parse::<u64>("user_id"), Ok(42));
+        assert_eq!(
+            params.parse::<u64>("id"),
+            Err(ParamError::Missing("id".to_owned()))
+        );
+        let err = params.parse::<u64>("name").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid param `name` = `trek`: invalid digit found in string"
+        );
+    }
+
+    #[test]
+    fn decode() {
+        let params = Params::from(vec![
+            ("user_id", "J%C3%BCrgen"),
+            ("bad", "J%FCrgen"),
+            ("short", "50%"),
+            ("sign", "%+1"),
+        ]);
+
+        assert_eq!(params.get("user_id"), Some("Jürgen"));
+        assert_eq!(params.raw("user_id"), Some("J%C3%BCrgen"));
+        assert_eq!(params.get("bad"), None);
+        assert_eq!(
+            params.try_get("bad"),
+            Err(ParamError::Decode {
+                name: "bad".to_owned(),
+                value: "J%FCrgen".to_owned(),
+            })
+        );
+        assert_eq!(
+            params.parse::<String>("short").unwrap_err().to_string(),
+            "param `short` = `50%` is not valid percent-encoded UTF-8"
+        );
+        assert_eq!(params.get("sign"), None);
+        assert_eq!(
+            params.try_get("id"),
+            Err(ParamError::Missing("id".to_owned()))
+        );
+        assert_eq!(
+            params,
+            [
+                ("user_id", "Jürgen"),
+                ("bad", "J%FCrgen"),
+                ("short", "50%"),
+                ("sign", "%+1")
+            ]
+        );
+
+        assert_eq!(encode("Jürgen/a b", false), "J%C3%BCrgen%2Fa%20b");
+        assert_eq!(encode("css/app.css", true), "css/app.css");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialize() {
+        #[derive(Debug, PartialEq, serde::Deserialize)]
+        struct Repo<'a> {
+            user_id: u64,
+            name: &'a str,
+            fork: Option<bool>,
+        }
+
+        let params = Params::from(vec![("user_id", "42"), ("name", "trek")]);
+        assert_eq!(
+            params.deserialize::<Repo>(),
+            Ok(Repo {
+                user_id: 42,
+                name: "trek",
+                fork: None,
+            })
+        );
+
+        let params = Params::from(vec![("user_id", "x"), ("name", "trek")]);
+        assert!(params.deserialize::<Repo>().is_err());
+
+        #[derive(Debug, PartialEq, serde::Deserialize)]
+        struct User {
+            name: String,
+        }
+
+        let params = Params::from(vec![("name", "J%C3%BCrgen")]);
+        assert_eq!(
+            params.deserialize::<User>(),
+            Ok(User {
+                name: "Jürgen".to_owned(),
+            })
+        );
+
+        let params = Params::from(vec![("name", "J%FCrgen")]);
+        assert_eq!(
+            params.deserialize::<User>(),
+            Err(ParamError::Decode {
+                name: "name".to_owned(),
+                value: "J%FCrgen".to_owned(),
+            })
+        );
+    }
+}
@@ -0,0 +1,193 @@
+use std::error::Error;
+use std::fmt;
+use std::ops::Deref;
+
+/// A validated route pattern, derefs to the pattern for `Router::handle`.
+///
+/// `route!` validates a literal pattern at compile time:
+///
+/// ```
+/// # #[macro_use] extern crate trek_router;
+/// # fn main() {
+/// let mut router = trek_router::Router::<fn() -> usize>::new();
+/// router.get(&route!("/users/:id"), || 0);
+/// # }
+/// ```
+///
+/// A param starts a segment and is followed by `/` or the end, its name is
+/// ASCII letters, digits and `_`. A wildcard is the last segment, and no two
+/// params share a name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Pattern<'a>(&'a str);
+
+impl<'a> Pattern<'a> {
+    /// Validates the pattern, panics if it is malformed, at compile time in a
+    /// const context.
+    pub const fn new(pattern: &'a str) -> Self {
+        match validate(pattern) {
+            Ok(()) => Pattern(pattern),
+            Err(e) => panic!("{}", e.message()),
+        }
+    }
+
+    /// Validates the pattern at runtime.
+    pub fn parse(pattern: &'a str) -> Result<Self, PatternError> {
+        validate(pattern).map(|()| Pattern(pattern))
+    }
+
+    pub const fn as_str(&self) -> &'a str {
+        self.0
+    }
+}
+
+impl<'a> Deref for Pattern<'a> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.0
+    }
+}
+
+impl<'a> fmt::Display for Pattern<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+/// Why a route pattern is malformed, see `Pattern`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PatternError {
+    /// The pattern does not start with `/`.
+    MissingSlash,
+    /// A `:` or `*` without a name.
+    EmptyName,
+    /// A `:` or `*` in the middle of a segment, e.g. `/users:id`.
+    Misplaced,
+    /// A param name followed by something other than `/`, e.g. `/:id.json`.
+    InvalidName,
+    /// A wildcard followed by more segments, e.g. `/*path/edit`.
+    WildcardNotLast,
+    /// Two params with the same name, e.g. `/:id/posts/:id`.
+    Duplicate,
+}
+
+impl PatternError {
+    const fn message(self) -> &'static str {
+        match self {
+            PatternError::MissingSlash => "the pattern must start with `/`",
+            PatternError::EmptyName => "a param must have a name",
+            PatternError::Misplaced => "a param must start a segment",
+            PatternError::InvalidName => {
+                "a param name must be ASCII letters, digits and `_`, followed by `/`"
+            }
+            PatternError::WildcardNotLast => "a wildcard must be the last segment",
+            PatternError::Duplicate => "param names must be unique",
+        }
+    }
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl Error for PatternError {}
+
+const fn validate(pattern: &str) -> Result<(), PatternError> {
+    let bytes = pattern.as_bytes();
+    if bytes.is_empty() || bytes[0] != b'/' {
+        return Err(PatternError::MissingSlash);
+    }
+    let mut i = 1;
+    while i < bytes.len() {
+        let c = bytes[i];
+        if c != b':' && c != b'*' {
+            i += 1;
+            continue;
+        }
+        if bytes[i - 1] != b'/' {
+            return Err(PatternError::Misplaced);
+        }
+        let end = name_end(bytes, i + 1);
+        if end == i + 1 {
+            return Err(PatternError::EmptyName);
+        }
+        if end < bytes.len() {
+            if c == b'*' {
+                return Err(PatternError::WildcardNotLast);
+            }
+            if bytes[end] != b'/' {
+                return Err(PatternError::InvalidName);
+            }
+        }
+        if seen(bytes, i + 1, end) {
+            return Err(PatternError::Duplicate);
+        }
+        i = end;
+    }
+    Ok(())
+}
+
+// the end of the name starting at `start`
+const fn name_end(bytes: &[u8], start: usize) -> usize {
+    let mut i = start;
+    while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+        i += 1;
+    }
+    i
+}
+
+// whether a param before `start` has the name `bytes[start..end]`
+const fn seen(bytes: &[u8], start: usize, end: usize) -> bool {
+    let mut i = 0;
+    while i + 1 < start {
+        if bytes[i] == b':' || bytes[i] == b'*' {
+            let other = name_end(bytes, i + 1);
+            if other - (i + 1) == end - start {
+                let mut j = 0;
+                while j < end - start && bytes[i + 1 + j] == bytes[start + j] {
+                    j += 1;
+                }
+                if j == end - start {
+                    return true;
+                }
+            }
+        }
+        i += 1;
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        for p in &[
+            "/",
+            "/users/:id",
+            "/users/:user_id/posts/:id",
+            "/static/*path",
+        ] {
+            assert_eq!(Pattern::parse(p).unwrap().as_str(), *p);
+        }
+
+        let err = |p| Pattern::parse(p).unwrap_err();
+        assert_eq!(err("users"), PatternError::MissingSlash);
+        assert_eq!(err("/users/:"), PatternError::EmptyName);
+        assert_eq!(err("/static/*"), PatternError::EmptyName);
+        assert_eq!(err("/users:id"), PatternError::Misplaced);
+        assert_eq!(err("/users/:id.json"), PatternError::InvalidName);
+        assert_eq!(err("/users/:id:name"), PatternError::InvalidName);
+        assert_eq!(err("/static/*path/edit"), PatternError::WildcardNotLast);
+        assert_eq!(err("/users/:id/posts/:id"), PatternError::Duplicate);
+        assert_eq!(err("/users/:id/*id"), PatternError::Duplicate);
+        assert!(Pattern::parse("/users/:id/posts/:i").is_ok());
+        assert_eq!(err("users").to_string(), "the pattern must start with `/`");
+
+        const USERS: Pattern<'static> = Pattern::new("/users/:id");
+        assert_eq!(&*USERS, "/users/:id");
+    }
+}
@@ -1,137 +1,548 @@
 use http::Method;
+use inflector::string::pluralize::to_plural;
+use inflector::string::singularize::to_singular;
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
 
 lazy_static! {
-    pub static ref RESOURCE_ACTIONS: &'static [(&'static str, &'static str, &'static Method)] = &[
-        ("show", "", &Method::GET),
-        ("create", "", &Method::POST),
-        ("update", "", &Method::PATCH),
-        ("update", "", &Method::PUT),
-        ("delete", "", &Method::DELETE),
-        ("edit", "edit", &Method::GET),
-        ("new", "new", &Method::GET),
+    pub static ref RESOURCE_ACTIONS: &'static [(Action, &'static str, &'static Method)] = &[
+        (Action::Show, "", &Method::GET),
+        (Action::Create, "", &Method::POST),
+        (Action::Update, "", &Method::PATCH),
+        (Action::Update, "", &Method::PUT),
+        (Action::Delete, "", &Method::DELETE),
+        (Action::Edit, "edit", &Method::GET),
+        (Action::New, "new", &Method::GET),
     ];
-    pub static ref RESOURCES_ACTIONS: &'static [(&'static str, &'static str, &'static Method)] = &[
-        ("index", "", &Method::GET),
-        ("create", "", &Method::POST),
-        ("new", "new", &Method::GET),
-        ("show", ":id", &Method::GET),
-        ("update", ":id", &Method::PATCH),
-        ("update", ":id", &Method::PUT),
-        ("delete", ":id", &Method::DELETE),
-        ("edit", ":id/edit", &Method::GET)
+    pub static ref RESOURCES_ACTIONS: &'static [(Action, &'static str, &'static Method)] = &[
+        (Action::Index, "", &Method::GET),
+        (Action::Create, "", &Method::POST),
+        (Action::New, "new", &Method::GET),
+        (Action::Show, ":id", &Method::GET),
+        (Action::Update, ":id", &Method::PATCH),
+        (Action::Update, ":id", &Method::PUT),
+        (Action::Delete, ":id", &Method::DELETE),
+        (Action::Edit, ":id/edit", &Method::GET)
     ];
 }
 
-#[derive(Default)]
-pub struct ResourceOptions {
-    only: Vec<&'static str>,
-    except: Vec<&'static str>,
+/// The standard actions of `Resource` and `Resources`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    Index,
+    Create,
+    New,
+    Show,
+    Update,
+    Delete,
+    Edit,
 }
 
-impl ResourceOptions {
-    pub fn only(only: Vec<&'static str>) -> Self {
+impl Action {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Action::Index => "index",
+            Action::Create => "create",
+            Action::New => "new",
+            Action::Show => "show",
+            Action::Update => "update",
+            Action::Delete => "delete",
+            Action::Edit => "edit",
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ResourceError {
+    /// Both `only` and `except` are set.
+    Conflict,
+    /// The action is not one of the resource, e.g. `index` of a `Resource`.
+    Unknown(Action),
+}
+
+impl fmt::Display for ResourceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ResourceError::Conflict => f.write_str("`only` and `except` can not be used together"),
+            ResourceError::Unknown(action) => write!(f, "unknown action `{}`", action),
+        }
+    }
+}
+
+impl Error for ResourceError {}
+
+/// A route generated by `Resource::build` or `Resources::build`.
+///
+/// The path is relative to the resource, `:id` is replaced with the id param
+/// of the resources, e.g. `:user_id`. A shallow route of nested resources is
+/// nested only under the member of the immediate parent, or not at all for a
+/// member route, see `ResourceOptions::shallow`.
+#[derive(Clone, Debug)]
+pub struct ResourceRoute<H> {
+    pub action: &'static str,
+    pub path: String,
+    pub method: Method,
+    pub handler: H,
+    pub shallow: bool,
+}
+
+impl<H> ResourceRoute<H> {
+    pub fn new(action: &'static str, path: &str, method: Method, handler: H) -> Self {
         Self {
-            only,
-            except: vec![],
+            action,
+            path: path.to_owned(),
+            method,
+            handler,
+            shallow: false,
         }
     }
 
-    pub fn except(except: Vec<&'static str>) -> Self {
+    /// Converts the handler, e.g. for boxing it.
+    pub fn map<T>(self, f: impl FnOnce(H) -> T) -> ResourceRoute<T> {
+        ResourceRoute {
+            action: self.action,
+            path: self.path,
+            method: self.method,
+            handler: f(self.handler),
+            shallow: self.shallow,
+        }
+    }
+}
+
+pub type Actions<C, B> = Vec<ResourceRoute<fn(C) -> B>>;
+
+/// Custom actions: the name, which is also the path segment, the method and
+/// the handler.
+pub type CustomActions<C, B> = Vec<(&'static str, Method, fn(C) -> B)>;
+
+/// The names of a resource, converted from the path given to
+/// `Router::resource` or `Router::resources`.
+///
+/// By default the last path segment is singularized or pluralized by Inflector,
+/// and the id param is the singular name with `_id`, e.g. `user_id` for
+/// `/users`. Words Inflector gets wrong can be set explicitly:
+///
+/// ```
+/// # use trek_router::ResourceName;
+/// ResourceName::new("/news").path("news").singular("story");
+/// ```
+#[derive(Clone, Debug)]
+pub struct ResourceName {
+    name: String,
+    path: Option<String>,
+    singular: Option<String>,
+    param: Option<String>,
+}
+
+impl ResourceName {
+    pub fn new(name: &str) -> Self {
         Self {
-            only: vec![],
-            except,
+            name: name.to_owned(),
+            path: None,
+            singular: None,
+            param: None,
         }
     }
+
+    /// The last path segment, used as is.
+    pub fn path(mut self, path: &str) -> Self {
+        self.path = Some(path.to_owned());
+        self
+    }
+
+    /// The singular name, the id param defaults to it with `_id`.
+    pub fn singular(mut self, singular: &str) -> Self {
+        self.singular = Some(singular.to_owned());
+        self
+    }
+
+    /// The id param, without `:`.
+    pub fn param(mut self, param: &str) -> Self {
+        self.param = Some(param.to_owned());
+        self
+    }
+
+    // the name without its last segment, e.g. `/v1/` for `/v1/users`
+    fn split(&self) -> (&str, &str) {
+        let name = self.name.trim_end_matches('/');
+        match name.rfind('/') {
+            Some(i) => (&name[..=i], &name[i + 1..]),
+            None => ("", name),
+        }
+    }
+
+    pub(crate) fn resource_path(&self) -> String {
+        let (prefix, last) = self.split();
+        prefix.to_owned() + &self.path.clone().unwrap_or_else(|| to_singular(last))
+    }
+
+    pub(crate) fn resources_path(&self) -> String {
+        let (prefix, last) = self.split();
+        prefix.to_owned() + &self.path.clone().unwrap_or_else(|| to_plural(last))
+    }
+
+    pub(crate) fn resources_param(&self) -> String {
+        if let Some(param) = &self.param {
+            return param.to_owned();
+        }
+        let singular = match &self.singular {
+            Some(singular) => singular.to_owned(),
+            None => to_singular(self.path.as_deref().unwrap_or_else(|| self.split().1)),
+        };
+        singular + "_id"
+    }
 }
 
-pub trait Resource {
-    type Context;
-    type Body;
+impl<'a> From<&'a str> for ResourceName {
+    fn from(name: &'a str) -> Self {
+        Self::new(name)
+    }
+}
 
-    fn show(ctx: Self::Context) -> Self::Body;
-    fn create(ctx: Self::Context) -> Self::Body;
-    fn update(ctx: Self::Context) -> Self::Body;
-    fn delete(ctx: Self::Context) -> Self::Body;
-    fn edit(ctx: Self::Context) -> Self::Body;
-    fn new(ctx: Self::Context) -> Self::Body;
+#[derive(Clone, Debug, Default)]
+pub struct ResourceOptions {
+    only: Vec<Action>,
+    except: Vec<Action>,
+    shallow: bool,
+}
 
-    fn build<'a>(
-        opts: ResourceOptions,
-    ) -> Vec<(
-        (&'a str, &'a str, &'a Method),
-        fn(Self::Context) -> Self::Body,
-    )> {
-        let ResourceOptions { only, except } = opts;
-        let mut ra: Vec<_> = RESOURCE_ACTIONS.to_vec();
+impl ResourceOptions {
+    /// Generates only the listed actions.
+    pub fn only(mut self, only: Vec<Action>) -> Self {
+        self.only = only;
+        self
+    }
+
+    /// Generates all but the listed actions.
+    pub fn except(mut self, except: Vec<Action>) -> Self {
+        self.except = except;
+        self
+    }
+
+    /// Routes the member actions of nested resources without the parents,
+    /// e.g. `/posts/:post_id` instead of `/users/:user_id/posts/:post_id`,
+    /// and nests the collection actions, e.g. `index`, only under the member
+    /// of the immediate parent, e.g. `/posts/:post_id/comments`.
+    pub fn shallow(mut self, shallow: bool) -> Self {
+        self.shallow = shallow;
+        self
+    }
+
+    // the standard actions of the table to generate
+    fn filter(
+        &self,
+        table: &[(Action, &'static str, &'static Method)],
+    ) -> Result<Vec<(Action, &'static str, &'static Method)>, ResourceError> {
+        let ResourceOptions { only, except, .. } = self;
+        if !only.is_empty() && !except.is_empty() {
+            return Err(ResourceError::Conflict);
+        }
+        if let Some(a) = only
+            .iter()
+            .chain(except.iter())
+            .find(|a| table.iter().all(|t| t.0 != **a))
+        {
+            return Err(ResourceError::Unknown(*a));
+        }
+        let mut ra = table.to_vec();
         if !only.is_empty() {
-            ra.retain(|t| !only.contains(&t.0));
+            ra.retain(|t| only.contains(&t.0));
         }
         if !except.is_empty() {
-            ra.retain(|t| except.contains(&t.0));
-        }
-        let mut r: Vec<(
-            (&'a str, &'a str, &'a Method),
-            fn(Self::Context) -> Self::Body,
-        )> = Vec::new();
-        for t in ra {
-            match t.0 {
-                "show" => r.push((t, Self::show)),
-                "create" => r.push((t, Self::create)),
-                "update" => r.push((t, Self::update)),
-                "delete" => r.push((t, Self::delete)),
-                "edit" => r.push((t, Self::edit)),
-                "new" => r.push((t, Self::new)),
-                _ => unimplemented!(),
-            }
+            ra.retain(|t| !except.contains(&t.0));
         }
-        r
+        Ok(ra)
+    }
+}
+
+/// A singular resource, e.g. `/geocoder`.
+///
+/// Each action returns its handler, the default `None` means the action is not
+/// provided and `build` skips it.
+pub trait Resource {
+    type Context;
+    type Body;
+
+    fn show() -> Option<fn(Self::Context) -> Self::Body> {
+        None
+    }
+    fn create() -> Option<fn(Self::Context) -> Self::Body> {
+        None
+    }
+    fn update() -> Option<fn(Self::Context) -> Self::Body> {
+        None
+    }
+    fn delete() -> Option<fn(Self::Context) -> Self::Body> {
+        None
+    }
+    fn edit() -> Option<fn(Self::Context) -> Self::Body> {
+        None
+    }
+    fn new() -> Option<fn(Self::Context) -> Self::Body> {
+        None
+    }
+
+    /// Extra actions, e.g. `("activate", Method::POST, Self::activate)` for
+    /// `POST /geocoder/activate`.
+    fn members() -> CustomActions<Self::Context, Self::Body> {
+        Vec::new()
+    }
+
+    fn build(opts: ResourceOptions) -> Result<Actions<Self::Context, Self::Body>, ResourceError> {
+        let ra = opts.filter(&RESOURCE_ACTIONS)?;
+        let handler = |action| match action {
+            Action::Show => Self::show(),
+            Action::Create => Self::create(),
+            Action::Update => Self::update(),
+            Action::Delete => Self::delete(),
+            Action::Edit => Self::edit(),
+            Action::New => Self::new(),
+            // rejected by `filter`
+            Action::Index => unreachable!(),
+        };
+        // a singular resource has no id, its members are routed like collections
+        Ok(routes(ra, handler, Self::members(), Vec::new(), false))
     }
 }
 
+/// A collection of resources, e.g. `/users` and `/users/:user_id`.
+///
+/// The actions are provided like those of `Resource`.
 pub trait Resources {
     type Context;
     type Body;
 
-    fn index(ctx: Self::Context) -> Self::Body;
-    fn create(ctx: Self::Context) -> Self::Body;
-    fn new(ctx: Self::Context) -> Self::Body;
-    fn show(ctx: Self::Context) -> Self::Body;
-    fn update(ctx: Self::Context) -> Self::Body;
-    fn delete(ctx: Self::Context) -> Self::Body;
-    fn edit(ctx: Self::Context) -> Self::Body;
+    fn index() -> Option<fn(Self::Context) -> Self::Body> {
+        None
+    }
+    fn create() -> Option<fn(Self::Context) -> Self::Body> {
+        None
+    }
+    fn new() -> Option<fn(Self::Context) -> Self::Body> {
+        None
+    }
+    fn show() -> Option<fn(Self::Context) -> Self::Body> {
+        None
+    }
+    fn update() -> Option<fn(Self::Context) -> Self::Body> {
+        None
+    }
+    fn delete() -> Option<fn(Self::Context) -> Self::Body> {
+        None
+    }
+    fn edit() -> Option<fn(Self::Context) -> Self::Body> {
+        None
+    }
+
+    /// Extra member actions, e.g. `("activate", Method::POST, Self::activate)`
+    /// for `POST /users/:user_id/activate`.
+    fn members() -> CustomActions<Self::Context, Self::Body> {
+        Vec::new()
+    }
+
+    /// Extra collection actions, e.g. `("search", Method::GET, Self::search)`
+    /// for `GET /users/search`.
+    fn collections() -> CustomActions<Self::Context, Self::Body> {
+        Vec::new()
+    }
+
+    fn build(opts: ResourceOptions) -> Result<Actions<Self::Context, Self::Body>, ResourceError> {
+        let ra = opts.filter(&RESOURCES_ACTIONS)?;
+        let handler = |action| match action {
+            Action::Index => Self::index(),
+            Action::Create => Self::create(),
+            Action::New => Self::new(),
+            Action::Show => Self::show(),
+            Action::Update => Self::update(),
+            Action::Delete => Self::delete(),
+            Action::Edit => Self::edit(),
+        };
+        Ok(routes(
+            ra,
+            handler,
+            Self::collections(),
+            Self::members(),
+            opts.shallow,
+        ))
+    }
+}
+
+/// A handler closing over a shared controller, built by `SharedResource::build`
+/// and `SharedResources::build`.
+pub type SharedHandler<C, B> = Arc<dyn Fn(C) -> B + Send + Sync>;
+
+pub type SharedActions<C, B> = Vec<ResourceRoute<SharedHandler<C, B>>>;
+
+/// An action of a shared controller, a method taking the context.
+pub type SharedFn<T, C, B> = fn(&T, C) -> B;
 
-    fn build<'a>(
+/// Custom actions of a shared controller, like `CustomActions` but the handler
+/// is a method, e.g. `("activate", Method::POST, Self::activate)`.
+pub type SharedCustomActions<T, C, B> = Vec<(&'static str, Method, SharedFn<T, C, B>)>;
+
+/// Like `Resource`, but the actions take `&self`, so the controller can hold
+/// state, e.g. a database pool. The controller is shared by its handlers.
+pub trait SharedResource: Send + Sync + Sized + 'static {
+    type Context: 'static;
+    type Body: 'static;
+
+    fn show(&self) -> Option<SharedFn<Self, Self::Context, Self::Body>> {
+        None
+    }
+    fn create(&self) -> Option<SharedFn<Self, Self::Context, Self::Body>> {
+        None
+    }
+    fn update(&self) -> Option<SharedFn<Self, Self::Context, Self::Body>> {
+        None
+    }
+    fn delete(&self) -> Option<SharedFn<Self, Self::Context, Self::Body>> {
+        None
+    }
+    fn edit(&self) -> Option<SharedFn<Self, Self::Context, Self::Body>> {
+        None
+    }
+    // named after the action
+    #[allow(clippy::wrong_self_convention)]
+    fn new(&self) -> Option<SharedFn<Self, Self::Context, Self::Body>> {
+        None
+    }
+
+    fn members(&self) -> SharedCustomActions<Self, Self::Context, Self::Body> {
+        Vec::new()
+    }
+
+    fn build(
+        self: Arc<Self>,
         opts: ResourceOptions,
-    ) -> Vec<(
-        (&'a str, &'a str, &'a Method),
-        fn(Self::Context) -> Self::Body,
-    )> {
-        let ResourceOptions { only, except } = opts;
-        let mut ra: Vec<_> = RESOURCES_ACTIONS.to_vec();
-        if !only.is_empty() {
-            ra.retain(|t| !only.contains(&t.0));
-        }
-        if !except.is_empty() {
-            ra.retain(|t| except.contains(&t.0));
-        }
-        let mut r: Vec<(
-            (&'a str, &'a str, &'a Method),
-            fn(Self::Context) -> Self::Body,
-        )> = Vec::new();
-        for t in ra {
-            match t.0 {
-                "index" => r.push((t, Self::index)),
-                "create" => r.push((t, Self::create)),
-                "new" => r.push((t, Self::new)),
-                "show" => r.push((t, Self::show)),
-                "update" => r.push((t, Self::update)),
-                "delete" => r.push((t, Self::delete)),
-                "edit" => r.push((t, Self::edit)),
-                _ => unimplemented!(),
-            }
+    ) -> Result<SharedActions<Self::Context, Self::Body>, ResourceError> {
+        let ra = opts.filter(&RESOURCE_ACTIONS)?;
+        let handler = |action| match action {
+            Action::Show => self.show(),
+            Action::Create => self.create(),
+            Action::Update => self.update(),
+            Action::Delete => self.delete(),
+            Action::Edit => self.edit(),
+            Action::New => self.new(),
+            // rejected by `filter`
+            Action::Index => unreachable!(),
+        };
+        let members = self.members();
+        Ok(routes(ra, handler, members, Vec::new(), false)
+            .into_iter()
+            .map(|r| r.map(|h| bind(&self, h)))
+            .collect())
+    }
+}
+
+/// The `SharedResource` counterpart of `Resources`.
+pub trait SharedResources: Send + Sync + Sized + 'static {
+    type Context: 'static;
+    type Body: 'static;
+
+    fn index(&self) -> Option<SharedFn<Self, Self::Context, Self::Body>> {
+        None
+    }
+    fn create(&self) -> Option<SharedFn<Self, Self::Context, Self::Body>> {
+        None
+    }
+    // named after the action
+    #[allow(clippy::wrong_self_convention)]
+    fn new(&self) -> Option<SharedFn<Self, Self::Context, Self::Body>> {
+        None
+    }
+    fn show(&self) -> Option<SharedFn<Self, Self::Context, Self::Body>> {
+        None
+    }
+    fn update(&self) -> Option<SharedFn<Self, Self::Context, Self::Body>> {
+        None
+    }
+    fn delete(&self) -> Option<SharedFn<Self, Self::Context, Self::Body>> {
+        None
+    }
+    fn edit(&self) -> Option<SharedFn<Self, Self::Context, Self::Body>> {
+        None
+    }
+
+    fn members(&self) -> SharedCustomActions<Self, Self::Context, Self::Body> {
+        Vec::new()
+    }
+
+    fn collections(&self) -> SharedCustomActions<Self, Self::Context, Self::Body> {
+        Vec::new()
+    }
+
+    fn build(
+        self: Arc<Self>,
+        opts: ResourceOptions,
+    ) -> Result<SharedActions<Self::Context, Self::Body>, ResourceError> {
+        let ra = opts.filter(&RESOURCES_ACTIONS)?;
+        let handler = |action| match action {
+            Action::Index => self.index(),
+            Action::Create => self.create(),
+            Action::New => self.new(),
+            Action::Show => self.show(),
+            Action::Update => self.update(),
+            Action::Delete => self.delete(),
+            Action::Edit => self.edit(),
+        };
+        let collections = self.collections();
+        let members = self.members();
+        Ok(routes(ra, handler, collections, members, opts.shallow)
+            .into_iter()
+            .map(|r| r.map(|h| bind(&self, h)))
+            .collect())
+    }
+}
+
+// binds a method to the shared controller
+fn bind<T, C, B>(this: &Arc<T>, method: SharedFn<T, C, B>) -> SharedHandler<C, B>
+where
+    T: Send + Sync + 'static,
+    C: 'static,
+    B: 'static,
+{
+    let this = this.clone();
+    Arc::new(move |ctx| method(&this, ctx))
+}
+
+// the routes of the standard actions, then the custom ones
+fn routes<T>(
+    ra: Vec<(Action, &'static str, &'static Method)>,
+    handler: impl Fn(Action) -> Option<T>,
+    collections: Vec<(&'static str, Method, T)>,
+    members: Vec<(&'static str, Method, T)>,
+    shallow: bool,
+) -> Vec<ResourceRoute<T>> {
+    let mut r = Vec::new();
+    for t in ra {
+        // skips the actions not provided
+        if let Some(handler) = handler(t.0) {
+            r.push(ResourceRoute::new(
+                t.0.as_str(),
+                t.1,
+                t.2.to_owned(),
+                handler,
+            ));
         }
-        r
     }
+    for (action, method, handler) in collections {
+        r.push(ResourceRoute::new(action, action, method, handler));
+    }
+    for (action, method, handler) in members {
+        let path = ":id/".to_owned() + action;
+        r.push(ResourceRoute::new(action, &path, method, handler));
+    }
+    for route in r.iter_mut() {
+        route.shallow = shallow;
+    }
+    r
 }
 
 #[cfg(test)]
@@ -153,38 +564,53 @@ mod tests {
             type Context = Context;
             type Body = usize;
 
-            fn show(ctx: Self::Context) -> Self::Body {
-                println!("{}", "Resource Show");
-                ctx.count + 0
+            fn show() -> Option<fn(Self::Context) -> Self::Body> {
+                Some(|ctx| {
+                    println!("Resource Show");
+                    ctx.count
+                })
             }
 
-            fn create(ctx: Self::Context) -> Self::Body {
-                println!("{}", "Resource Create");
-                ctx.count + 1
+            fn create() -> Option<fn(Self::Context) -> Self::Body> {
+                Some(|ctx| {
+                    println!("Resource Create");
+                    ctx.count + 1
+                })
             }
 
-            fn update(ctx: Self::Context) -> Self::Body {
-                println!("{}", "Resource Update");
-                ctx.count + 2
+            fn update() -> Option<fn(Self::Context) -> Self::Body> {
+                Some(|ctx| {
+                    println!("Resource Update");
+                    ctx.count + 2
+                })
             }
 
-            fn delete(ctx: Self::Context) -> Self::Body {
-                println!("{}", "Resource Delete");
-                ctx.count + 3
+            fn delete() -> Option<fn(Self::Context) -> Self::Body> {
+                Some(|ctx| {
+                    println!("Resource Delete");
+                    ctx.count + 3
+                })
             }
 
-            fn edit(ctx: Self::Context) -> Self::Body {
-                println!("{}", "Resource Edit");
-                ctx.count + 4
+            fn edit() -> Option<fn(Self::Context) -> Self::Body> {
+                Some(|ctx| {
+                    println!("Resource Edit");
+                    ctx.count + 4
+                })
             }
 
-            fn new(ctx: Self::Context) -> Self::Body {
-                println!("{}", "Resource New");
-                ctx.count + 5
+            fn new() -> Option<fn(Self::Context) -> Self::Body> {
+                Some(|ctx| {
+                    println!("Resource New");
+                    ctx.count + 5
+                })
             }
         }
 
-        router.resource("/geocoder", Geocoder::build(ResourceOptions::default()));
+        router.resource(
+            "/geocoder",
+            Geocoder::build(ResourceOptions::default()).unwrap(),
+        );
 
         struct Users {}
 
@@ -192,43 +618,57 @@ mod tests {
             type Context = Context;
             type Body = usize;
 
-            fn index(ctx: Self::Context) -> Self::Body {
-                println!("{}", "Resources Index");
-                ctx.count + 0
+            fn index() -> Option<fn(Self::Context) -> Self::Body> {
+                Some(|ctx| {
+                    println!("Resources Index");
+                    ctx.count
+                })
             }
 
-            fn create(ctx: Self::Context) -> Self::Body {
-                println!("{}", "Resources Create");
-                ctx.count + 1
+            fn create() -> Option<fn(Self::Context) -> Self::Body> {
+                Some(|ctx| {
+                    println!("Resources Create");
+                    ctx.count + 1
+                })
             }
 
-            fn new(ctx: Self::Context) -> Self::Body {
-                println!("{}", "Resources New");
-                ctx.count + 2
+            fn new() -> Option<fn(Self::Context) -> Self::Body> {
+                Some(|ctx| {
+                    println!("Resources New");
+                    ctx.count + 2
+                })
             }
 
-            fn show(ctx: Self::Context) -> Self::Body {
-                println!("{}", "Resources Show");
-                ctx.count + 3
+            fn show() -> Option<fn(Self::Context) -> Self::Body> {
+                Some(|ctx| {
+                    println!("Resources Show");
+                    ctx.count + 3
+                })
             }
 
-            fn update(ctx: Self::Context) -> Self::Body {
-                println!("{}", "Resources Update");
-                ctx.count + 4
+            fn update() -> Option<fn(Self::Context) -> Self::Body> {
+                Some(|ctx| {
+                    println!("Resources Update");
+                    ctx.count + 4
+                })
             }
 
-            fn delete(ctx: Self::Context) -> Self::Body {
-                println!("{}", "Resources Delete");
-                ctx.count + 5
+            fn delete() -> Option<fn(Self::Context) -> Self::Body> {
+                Some(|ctx| {
+                    println!("Resources Delete");
+                    ctx.count + 5
+                })
             }
 
-            fn edit(ctx: Self::Context) -> Self::Body {
-                println!("{}", "Resources Edit");
-                ctx.count + 6
+            fn edit() -> Option<fn(Self::Context) -> Self::Body> {
+                Some(|ctx| {
+                    println!("Resources Edit");
+                    ctx.count + 6
+                })
             }
         }
 
-        router.resources("/users", Users::build(ResourceOptions::default()));
+        router.resources("/users", Users::build(ResourceOptions::default()).unwrap());
 
         dbg!(&router);
 
@@ -278,63 +718,486 @@ mod tests {
         let r = router.find(&Method::GET, "/geocoder/new");
         assert!(r.is_some());
         let (h, p) = r.unwrap();
-        assert_eq!(h((ctx)), 5);
+        assert_eq!(h(ctx), 5);
         assert_eq!(p, []);
 
         let ctx = Context { count: 0 };
         let r = router.find(&Method::GET, "/users");
         assert!(r.is_some());
         let (h, p) = r.unwrap();
-        assert_eq!(h((ctx)), 0);
+        assert_eq!(h(ctx), 0);
         assert_eq!(p, []);
 
         let ctx = Context { count: 0 };
         let r = router.find(&Method::POST, "/users");
         assert!(r.is_some());
         let (h, p) = r.unwrap();
-        assert_eq!(h((ctx)), 1);
+        assert_eq!(h(ctx), 1);
         assert_eq!(p, []);
 
         let ctx = Context { count: 0 };
         let r = router.find(&Method::GET, "/users/new");
         assert!(r.is_some());
         let (h, p) = r.unwrap();
-        assert_eq!(h((ctx)), 2);
+        assert_eq!(h(ctx), 2);
         assert_eq!(p, []);
 
         let ctx = Context { count: 0 };
         let r = router.find(&Method::GET, "/users/1");
         assert!(r.is_some());
         let (h, p) = r.unwrap();
-        assert_eq!(h((ctx)), 3);
+        assert_eq!(h(ctx), 3);
         assert_eq!(p, [("user_id", "1")]);
 
         let ctx = Context { count: 0 };
         let r = router.find(&Method::PATCH, "/users/1");
         assert!(r.is_some());
         let (h, p) = r.unwrap();
-        assert_eq!(h((ctx)), 4);
+        assert_eq!(h(ctx), 4);
         assert_eq!(p, [("user_id", "1")]);
 
         let ctx = Context { count: 0 };
         let r = router.find(&Method::PUT, "/users/1");
         assert!(r.is_some());
         let (h, p) = r.unwrap();
-        assert_eq!(h((ctx)), 4);
+        assert_eq!(h(ctx), 4);
         assert_eq!(p, [("user_id", "1")]);
 
         let ctx = Context { count: 0 };
         let r = router.find(&Method::DELETE, "/users/1");
         assert!(r.is_some());
         let (h, p) = r.unwrap();
-        assert_eq!(h((ctx)), 5);
+        assert_eq!(h(ctx), 5);
         assert_eq!(p, [("user_id", "1")]);
 
         let ctx = Context { count: 0 };
         let r = router.find(&Method::GET, "/users/1/edit");
         assert!(r.is_some());
         let (h, p) = r.unwrap();
-        assert_eq!(h((ctx)), 6);
+        assert_eq!(h(ctx), 6);
+        assert_eq!(p, [("user_id", "1")]);
+
+        let r = router
+            .routes()
+            .find(|r| r.method() == Method::GET && r.path() == "/users/:user_id")
+            .unwrap();
+        assert_eq!(r.action(), Some("show"));
+    }
+
+    #[test]
+    fn nested_resources() {
+        type F = Arc<dyn Fn() -> String + Send + Sync>;
+
+        // boxes the resource handlers so middleware can wrap them
+        fn boxed(actions: Actions<(), usize>) -> Vec<ResourceRoute<F>> {
+            actions
+                .into_iter()
+                .map(|r| r.map(|h| Arc::new(move || h(()).to_string()) as F))
+                .collect()
+        }
+
+        let mut router = Router::<F>::new();
+
+        router.scope("/v1", |v1| {
+            v1.middleware(|next: F| -> F { Arc::new(move || format!("v1:{}", next())) })
+                .resources_with(
+                    "/users",
+                    boxed(Users::build(ResourceOptions::default()).unwrap()),
+                    |u| {
+                        u.resources_with(
+                            "/posts",
+                            boxed(Users::build(ResourceOptions::default()).unwrap()),
+                            |p| {
+                                p.resources(
+                                    "/comments",
+                                    boxed(Users::build(ResourceOptions::default()).unwrap()),
+                                );
+                            },
+                        );
+                    },
+                );
+        });
+
+        let (h, p) = router.find(&Method::GET, "/v1/users/1").unwrap();
+        assert_eq!(h(), "v1:3");
+        assert_eq!(p, [("user_id", "1")]);
+
+        let (h, p) = router.find(&Method::GET, "/v1/users/1/posts").unwrap();
+        assert_eq!(h(), "v1:0");
         assert_eq!(p, [("user_id", "1")]);
+
+        let (h, p) = router.find(&Method::PATCH, "/v1/users/1/posts/2").unwrap();
+        assert_eq!(h(), "v1:4");
+        assert_eq!(p, [("user_id", "1"), ("post_id", "2")]);
+
+        let (h, p) = router
+            .find(&Method::GET, "/v1/users/1/posts/2/comments/3/edit")
+            .unwrap();
+        assert_eq!(h(), "v1:6");
+        assert_eq!(p, [("user_id", "1"), ("post_id", "2"), ("comment_id", "3")]);
+    }
+
+    #[test]
+    fn custom_actions() {
+        type F = fn(()) -> usize;
+
+        // shadows the counting fixtures, only custom actions matter here
+        struct Account {}
+
+        impl Resource for Account {
+            type Context = ();
+            type Body = usize;
+
+            fn members() -> CustomActions<(), usize> {
+                vec![("verify", Method::POST, |_| 6)]
+            }
+        }
+
+        struct Users {}
+
+        impl Resources for Users {
+            type Context = ();
+            type Body = usize;
+
+            fn show() -> Option<fn(()) -> usize> {
+                Some(|_| 3)
+            }
+
+            fn members() -> CustomActions<(), usize> {
+                vec![
+                    ("activate", Method::POST, |_| 7),
+                    ("avatar", Method::GET, |_| 8),
+                ]
+            }
+
+            fn collections() -> CustomActions<(), usize> {
+                vec![("search", Method::GET, |_| 9)]
+            }
+        }
+
+        let mut router = Router::<F>::new();
+        router
+            .resource(
+                "/account",
+                Account::build(ResourceOptions::default()).unwrap(),
+            )
+            .resources("/users", Users::build(ResourceOptions::default()).unwrap());
+
+        let (h, p) = router.find(&Method::POST, "/account/verify").unwrap();
+        assert_eq!(h(()), 6);
+        assert_eq!(p, []);
+
+        let (h, p) = router.find(&Method::POST, "/users/1/activate").unwrap();
+        assert_eq!(h(()), 7);
+        assert_eq!(p, [("user_id", "1")]);
+
+        let (h, p) = router.find(&Method::GET, "/users/1/avatar").unwrap();
+        assert_eq!(h(()), 8);
+        assert_eq!(p, [("user_id", "1")]);
+
+        let (h, p) = router.find(&Method::GET, "/users/search").unwrap();
+        assert_eq!(h(()), 9);
+        assert_eq!(p, []);
+
+        let (h, _) = router.find(&Method::GET, "/users/1").unwrap();
+        assert_eq!(h(()), 3);
+
+        let r = router
+            .routes()
+            .find(|r| r.path() == "/users/:user_id/activate")
+            .unwrap();
+        assert_eq!(r.action(), Some("activate"));
+    }
+
+    // counting resources, each action returns its position
+    struct Account {}
+
+    impl Resource for Account {
+        type Context = ();
+        type Body = usize;
+
+        fn show() -> Option<fn(()) -> usize> {
+            Some(|_| 0)
+        }
+        fn create() -> Option<fn(()) -> usize> {
+            Some(|_| 1)
+        }
+        fn update() -> Option<fn(()) -> usize> {
+            Some(|_| 2)
+        }
+        fn delete() -> Option<fn(()) -> usize> {
+            Some(|_| 3)
+        }
+        fn edit() -> Option<fn(()) -> usize> {
+            Some(|_| 4)
+        }
+        fn new() -> Option<fn(()) -> usize> {
+            Some(|_| 5)
+        }
+    }
+
+    struct Users {}
+
+    impl Resources for Users {
+        type Context = ();
+        type Body = usize;
+
+        fn index() -> Option<fn(()) -> usize> {
+            Some(|_| 0)
+        }
+        fn create() -> Option<fn(()) -> usize> {
+            Some(|_| 1)
+        }
+        fn new() -> Option<fn(()) -> usize> {
+            Some(|_| 2)
+        }
+        fn show() -> Option<fn(()) -> usize> {
+            Some(|_| 3)
+        }
+        fn update() -> Option<fn(()) -> usize> {
+            Some(|_| 4)
+        }
+        fn delete() -> Option<fn(()) -> usize> {
+            Some(|_| 5)
+        }
+        fn edit() -> Option<fn(()) -> usize> {
+            Some(|_| 6)
+        }
+    }
+
+    #[test]
+    fn options() {
+        let actions = |r: Actions<(), usize>| r.iter().map(|r| r.action).collect::<Vec<_>>();
+
+        let r = Users::build(ResourceOptions::default().only(vec![Action::Index, Action::Show]));
+        assert_eq!(actions(r.unwrap()), ["index", "show"]);
+
+        let r = Users::build(ResourceOptions::default().except(vec![Action::Update, Action::New]));
+        assert_eq!(
+            actions(r.unwrap()),
+            ["index", "create", "show", "delete", "edit"]
+        );
+
+        let r = Account::build(ResourceOptions::default().only(vec![Action::Update]));
+        assert_eq!(actions(r.unwrap()), ["update", "update"]);
+
+        let r = Users::build(
+            ResourceOptions::default()
+                .only(vec![Action::Show])
+                .except(vec![Action::Edit]),
+        );
+        assert_eq!(r.unwrap_err(), ResourceError::Conflict);
+
+        let r = Account::build(ResourceOptions::default().except(vec![Action::Index]));
+        let err = r.unwrap_err();
+        assert_eq!(err, ResourceError::Unknown(Action::Index));
+        assert_eq!(err.to_string(), "unknown action `index`");
+    }
+
+    #[test]
+    fn not_provided() {
+        type F = fn(()) -> usize;
+        let mut router = Router::<F>::new();
+
+        struct Photos {}
+
+        impl Resources for Photos {
+            type Context = ();
+            type Body = usize;
+
+            fn index() -> Option<fn(()) -> usize> {
+                Some(|_| 0)
+            }
+            fn show() -> Option<fn(()) -> usize> {
+                Some(|_| 3)
+            }
+        }
+
+        let r = Photos::build(ResourceOptions::default()).unwrap();
+        let actions: Vec<_> = r.iter().map(|r| r.action).collect();
+        assert_eq!(actions, ["index", "show"]);
+
+        let r = Photos::build(ResourceOptions::default().only(vec![Action::Show, Action::Edit]));
+        assert_eq!(r.unwrap().len(), 1);
+
+        router.resources(
+            "/photos",
+            Photos::build(ResourceOptions::default()).unwrap(),
+        );
+        match router.lookup(&Method::DELETE, "/photos/1") {
+            crate::Match::MethodNotAllowed(allowed) => assert_eq!(allowed, [Method::GET]),
+            _ => panic!("expected 405"),
+        }
+        assert!(router
+            .lookup(&Method::GET, "/photos/1/edit")
+            .found()
+            .is_none());
+    }
+
+    #[test]
+    fn names() {
+        type F = fn(()) -> usize;
+        let mut router = Router::<F>::new();
+
+        router
+            .resource(
+                ResourceName::new("/profile").path("me"),
+                Account::build(ResourceOptions::default().only(vec![Action::Show])).unwrap(),
+            )
+            .resources(
+                ResourceName::new("/news").singular("story"),
+                Users::build(ResourceOptions::default().only(vec![Action::Show])).unwrap(),
+            )
+            .resources(
+                ResourceName::new("data").param("key"),
+                Users::build(ResourceOptions::default().only(vec![Action::Show])).unwrap(),
+            )
+            .resources(
+                ResourceName::new("people")
+                    .path("people")
+                    .singular("person"),
+                Users::build(ResourceOptions::default().only(vec![Action::Show])).unwrap(),
+            )
+            .resources(
+                "/v1/users",
+                Users::build(ResourceOptions::default().only(vec![Action::Show])).unwrap(),
+            );
+
+        let paths: Vec<_> = router.routes().map(|r| r.path()).collect();
+        assert_eq!(
+            paths,
+            [
+                "/me",
+                "/news/:story_id",
+                "/data/:key",
+                "/people/:person_id",
+                "/v1/users/:user_id"
+            ]
+        );
+    }
+
+    #[test]
+    fn shallow() {
+        type F = fn(()) -> usize;
+        let mut router = Router::<F>::new();
+
+        router.scope("/v1", |v1| {
+            v1.resources_with(
+                "/users",
+                Users::build(ResourceOptions::default().only(vec![Action::Show])).unwrap(),
+                |u| {
+                    u.resources_with(
+                        "/posts",
+                        Users::build(ResourceOptions::default().shallow(true)).unwrap(),
+                        |p| {
+                            p.resources(
+                                "/comments",
+                                Users::build(
+                                    ResourceOptions::default()
+                                        .only(vec![Action::Index, Action::Show])
+                                        .shallow(true),
+                                )
+                                .unwrap(),
+                            );
+                        },
+                    );
+                },
+            );
+        });
+
+        let routes: Vec<_> = router
+            .routes()
+            .map(|r| (r.action().unwrap(), r.path()))
+            .collect();
+        assert_eq!(
+            routes,
+            [
+                ("show", "/v1/users/:user_id"),
+                ("index", "/v1/users/:user_id/posts"),
+                ("create", "/v1/users/:user_id/posts"),
+                ("new", "/v1/users/:user_id/posts/new"),
+                ("show", "/v1/posts/:post_id"),
+                ("update", "/v1/posts/:post_id"),
+                ("update", "/v1/posts/:post_id"),
+                ("delete", "/v1/posts/:post_id"),
+                ("edit", "/v1/posts/:post_id/edit"),
+                ("index", "/v1/posts/:post_id/comments"),
+                ("show", "/v1/comments/:comment_id"),
+            ]
+        );
+    }
+
+    #[test]
+    fn shared_resources() {
+        type F = SharedHandler<String, String>;
+        let mut router = Router::<F>::new();
+
+        struct Users {
+            db: Vec<&'static str>,
+        }
+
+        impl SharedResources for Users {
+            type Context = String;
+            type Body = String;
+
+            fn index(&self) -> Option<fn(&Self, String) -> String> {
+                Some(Users::list)
+            }
+            fn create(&self) -> Option<fn(&Self, String) -> String> {
+                Some(|_, name| format!("create {}", name))
+            }
+            fn show(&self) -> Option<fn(&Self, String) -> String> {
+                Some(Users::find)
+            }
+
+            fn members(&self) -> SharedCustomActions<Self, String, String> {
+                vec![("activate", Method::POST, Users::activate)]
+            }
+        }
+
+        impl Users {
+            fn list(&self, _: String) -> String {
+                self.db.join(",")
+            }
+
+            fn find(&self, id: String) -> String {
+                self.db[id.parse::<usize>().unwrap()].to_owned()
+            }
+
+            fn activate(&self, id: String) -> String {
+                format!("activate {}", self.find(id))
+            }
+        }
+
+        let users = Arc::new(Users {
+            db: vec!["alice", "bob"],
+        });
+        router.resources(
+            "/users",
+            users
+                .clone()
+                .build(ResourceOptions::default().except(vec![Action::Create]))
+                .unwrap(),
+        );
+        assert_eq!(Arc::strong_count(&users), 4);
+
+        let (h, _) = router.find(&Method::GET, "/users").unwrap();
+        assert_eq!(h(String::new()), "alice,bob");
+        let (h, p) = router.find(&Method::GET, "/users/1").unwrap();
+        assert_eq!(h(p.get("user_id").unwrap().to_owned()), "bob");
+        let (h, _) = router.find(&Method::POST, "/users/0/activate").unwrap();
+        assert_eq!(h("0".to_owned()), "activate alice");
+        assert!(router.find(&Method::GET, "/users/1/edit").is_none());
+
+        assert_eq!(
+            users
+                .build(
+                    ResourceOptions::default()
+                        .only(vec![Action::Index])
+                        .except(vec![Action::New])
+                )
+                .err(),
+            Some(ResourceError::Conflict)
+        );
     }
 }
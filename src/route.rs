@@ -0,0 +1,70 @@
+use http::Method;
+use std::fmt;
+
+/// A registered route, as listed by `Router::routes`.
+#[derive(Clone, Debug)]
+pub struct Route {
+    pub(crate) method: Method,
+    pub(crate) path: String,
+    pub(crate) name: Option<String>,
+    pub(crate) action: Option<String>,
+}
+
+impl Route {
+    pub fn method(&self) -> &Method {
+        &self.method
+    }
+
+    /// The full pattern, including the scope prefix.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// The action of a route generated by `resource` or `resources`.
+    pub fn action(&self) -> Option<&str> {
+        self.action.as_deref()
+    }
+}
+
+/// Formats routes as a table, like `rake routes`.
+///
+/// ```text
+///  Name Method Path           Action
+/// login GET    /v1/login
+///       GET    /users/:user_id show
+/// ```
+#[derive(Debug)]
+pub struct RouteTable<'a>(pub(crate) &'a [Route]);
+
+impl<'a> fmt::Display for RouteTable<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let width = |title: &str, column: &dyn Fn(&Route) -> &str| {
+            self.0
+                .iter()
+                .map(|r| column(r).len())
+                .fold(title.len(), usize::max)
+        };
+        let name = width("Name", &|r| r.name().unwrap_or(""));
+        let method = width("Method", &|r| r.method.as_str());
+        let path = width("Path", &|r| &r.path);
+
+        let mut line = |n: &str, m: &str, p: &str, a: &str| {
+            let line = format!("{:>name$} {:method$} {:path$} {}", n, m, p, a);
+            writeln!(f, "{}", line.trim_end())
+        };
+        line("Name", "Method", "Path", "Action")?;
+        for r in self.0 {
+            line(
+                r.name().unwrap_or(""),
+                r.method.as_str(),
+                &r.path,
+                r.action().unwrap_or(""),
+            )?;
+        }
+        Ok(())
+    }
+}
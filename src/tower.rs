@@ -0,0 +1,251 @@
+//! A Tower `Service` dispatching requests to a router of services.
+
+use crate::dispatch::{dispatch, Dispatch};
+use crate::Router;
+use http::{Request, Response};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tower_service::Service;
+
+/// Dispatches a request to the service routed by `Router::lookup`, with the
+/// `Params` in the request extensions. Other matches are answered as listed on
+/// `Match`.
+pub struct RouterService<S> {
+    router: Arc<Router<S>>,
+}
+
+impl<S> RouterService<S> {
+    pub fn new(router: Router<S>) -> Self {
+        Self {
+            router: Arc::new(router),
+        }
+    }
+}
+
+impl<S> Clone for RouterService<S> {
+    fn clone(&self) -> Self {
+        Self {
+            router: Arc::clone(&self.router),
+        }
+    }
+}
+
+impl<S, B, RB> Service<Request<B>> for RouterService<S>
+where
+    S: Service<Request<B>, Response = Response<RB>> + Clone,
+    RB: Default,
+{
+    type Response = Response<RB>;
+    type Error = S::Error;
+    type Future = ResponseFuture<S, B, RB>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        // the routed service is polled by the future
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, mut req: Request<B>) -> Self::Future {
+        let (service, params, head) = match dispatch(&self.router, &req) {
+            Dispatch::Handler(service, params, head) => (service, params, head),
+            Dispatch::Response(res) => {
+                return ResponseFuture {
+                    state: State::Status(Some(res)),
+                    head: false,
+                }
+            }
+        };
+        req.extensions_mut().insert(params);
+        ResponseFuture {
+            state: State::Ready(service, Some(req)),
+            head,
+        }
+    }
+}
+
+/// The response future of `RouterService`.
+pub struct ResponseFuture<S, B, RB>
+where
+    S: Service<Request<B>>,
+{
+    state: State<S, B, RB>,
+    // discards the body for an automatic HEAD
+    head: bool,
+}
+
+enum State<S, B, RB>
+where
+    S: Service<Request<B>>,
+{
+    // waits for the routed service to be ready
+    Ready(S, Option<Request<B>>),
+    Called(Pin<Box<S::Future>>),
+    Status(Option<Response<RB>>),
+}
+
+// no field is pinned, the routed future is boxed
+impl<S, B, RB> Unpin for ResponseFuture<S, B, RB> where S: Service<Request<B>> {}
+
+impl<S, B, RB> Future for ResponseFuture<S, B, RB>
+where
+    S: Service<Request<B>, Response = Response<RB>>,
+    RB: Default,
+{
+    type Output = Result<Response<RB>, S::Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        loop {
+            let head = self.head;
+            let next = match &mut self.state {
+                State::Ready(service, req) => match service.poll_ready(cx) {
+                    Poll::Ready(Ok(())) => {
+                        let req = req.take().expect("polled after completion");
+                        State::Called(Box::pin(service.call(req)))
+                    }
+                    Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+                    Poll::Pending => return Poll::Pending,
+                },
+                State::Called(future) => {
+                    return future.as_mut().poll(cx).map(|res| {
+                        res.map(|res| {
+                            if head {
+                                res.map(|_| RB::default())
+                            } else {
+                                res
+                            }
+                        })
+                    })
+                }
+                State::Status(res) => {
+                    return Poll::Ready(Ok(res.take().expect("polled after completion")))
+                }
+            };
+            self.state = next;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Cors, Params};
+    use http::header::{
+        ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_REQUEST_METHOD,
+        ALLOW, LOCATION, ORIGIN,
+    };
+    use http::{Method, StatusCode};
+    use std::convert::Infallible;
+    use std::future::{ready, Ready};
+    use std::task::Waker;
+
+    #[derive(Clone)]
+    struct Hello(&'static str);
+
+    impl Service<Request<String>> for Hello {
+        type Response = Response<String>;
+        type Error = Infallible;
+        type Future = Ready<Result<Response<String>, Infallible>>;
+
+        fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, req: Request<String>) -> Self::Future {
+            let params = req.extensions().get::<Params>().unwrap();
+            let name = params.get("name").unwrap_or("world");
+            ready(Ok(Response::new(format!("{}, {}!", self.0, name))))
+        }
+    }
+
+    fn call(service: &mut RouterService<Hello>, method: Method, path: &str) -> Response<String> {
+        let req = Request::builder()
+            .method(method)
+            .uri(path)
+            .body(String::new())
+            .unwrap();
+        send(service, req)
+    }
+
+    fn send(service: &mut RouterService<Hello>, req: Request<String>) -> Response<String> {
+        let mut cx = Context::from_waker(Waker::noop());
+        assert!(service.poll_ready(&mut cx).is_ready());
+        match Pin::new(&mut service.call(req)).poll(&mut cx) {
+            Poll::Ready(res) => res.unwrap(),
+            Poll::Pending => panic!("pending"),
+        }
+    }
+
+    #[test]
+    fn service() {
+        let mut router = Router::new();
+        router
+            .redirect_trailing_slash(true)
+            .auto_head(true)
+            .get("/", Hello("Hello"))
+            .get("/hello/:name", Hello("Hello"))
+            .post("/hello/:name", Hello("Hi"));
+        let mut service = RouterService::new(router);
+
+        let res = call(&mut service, Method::GET, "/hello/trek");
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.body(), "Hello, trek!");
+
+        let res = call(&mut service, Method::POST, "/hello/trek");
+        assert_eq!(res.body(), "Hi, trek!");
+
+        // the GET handler without the body
+        let res = call(&mut service, Method::HEAD, "/hello/trek");
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.body(), "");
+
+        let res = call(&mut service, Method::GET, "/");
+        assert_eq!(res.body(), "Hello, world!");
+
+        let res = call(&mut service, Method::GET, "/users");
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        assert_eq!(res.body(), "");
+
+        let res = call(&mut service, Method::DELETE, "/hello/trek");
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(res.headers()[ALLOW], "GET, HEAD, POST");
+
+        let res = call(&mut service, Method::GET, "/hello/trek/");
+        assert_eq!(res.status(), StatusCode::MOVED_PERMANENTLY);
+        assert_eq!(res.headers()[LOCATION], "/hello/trek");
+
+        let res = call(&mut service, Method::GET, "/hello/trek/?lang=en");
+        assert_eq!(res.headers()[LOCATION], "/hello/trek?lang=en");
+    }
+
+    #[test]
+    fn preflight() {
+        let mut router = Router::new();
+        router
+            .auto_options(true)
+            .cors(Cors::new().origin("https://example.com"))
+            .get("/hello/:name", Hello("Hello"));
+        let mut service = RouterService::new(router);
+
+        let req = Request::builder()
+            .method(Method::OPTIONS)
+            .uri("/hello/trek")
+            .header(ORIGIN, "https://example.com")
+            .header(ACCESS_CONTROL_REQUEST_METHOD, "GET")
+            .body(String::new())
+            .unwrap();
+        let res = send(&mut service, req);
+        assert_eq!(res.status(), StatusCode::NO_CONTENT);
+        assert_eq!(res.headers()[ALLOW], "GET, OPTIONS");
+        assert_eq!(
+            res.headers()[ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://example.com"
+        );
+        assert_eq!(res.headers()[ACCESS_CONTROL_ALLOW_METHODS], "GET, OPTIONS");
+
+        // a plain OPTIONS has no CORS headers
+        let res = call(&mut service, Method::OPTIONS, "/hello/trek");
+        assert_eq!(res.status(), StatusCode::NO_CONTENT);
+        assert!(!res.headers().contains_key(ACCESS_CONTROL_ALLOW_ORIGIN));
+    }
+}